@@ -1,7 +1,9 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contracterror, token, Address, Env, String, Symbol,
+    contract, contractclient, contractimpl, contracttype, contracterror, token, xdr::ToXdr,
+    Address, Bytes, Env, IntoVal, Symbol,
+    Topics, Val, Vec,
 };
 
 // ─── Storage keys ───────────────────────────────────────────────────────────
@@ -12,8 +14,78 @@ enum DataKey {
     Balance(Address, Address),        // (owner, token) → i128
     Lock(Address, u64),               // (owner, lock_id) → LockEntry
     NextLockId(Address),              // owner → u64
+    EventLevel,                       // u32 — see EVENTS_* below
+    SwapAdapter,                      // Address — DEX adapter for lock_swapped
+    MaxLockDuration,                  // u64 — max ledgers a lock may span
+    FeeBps,                           // u32 — release fee in basis points
+    TotalBalance(Address),            // token → i128, sum of available balances
+    TotalLocked(Address),             // token → i128, sum of active lock amounts
+    WithdrawDelay,                    // WithdrawDelay
+    PendingWithdraw(Address, Address), // (owner, token) → PendingWithdraw
+    Allowance(Address, Address, Address), // (owner, spender, token) → i128
+    IncomingLocks(Address),           // beneficiary → Vec<(owner, lock_id)>
+    ExpireOnRead,                     // bool — report past-expiry locks as Expired
+    MinDeposit(Address),              // token → i128, smallest accepted deposit
+    DepositCap(Address),              // token → i128, max available balance per owner
+    TokenFeeBps(Address),             // token → u32, overrides FeeBps for that token
+    Offer(Address, u64),              // (owner, lock_id) → recipient of an offered release
+    WithdrawLimit,                    // WithdrawLimit
+    WithdrawWindow(Address, Address), // (owner, token) → WithdrawWindow
+    SelfFrozen(Address),              // owner → bool, set via freeze_self
+    ChallengeWindow,                  // u64 — ledgers after expiry a beneficiary may challenge
+    Challenge(Address, u64),          // (owner, lock_id) → ledger the challenge was filed
+    Reserve(Address),                 // token → i128, pre-funded pool paying lock interest
+    UniqueOwners,                     // u64 — addresses that have ever deposited
+    OwnerSeen(Address),               // owner → bool, counted in UniqueOwners
+    OwnerTokens(Address),             // owner → Vec<Address>, tokens ever credited
+    Basket(Address, u64),             // (owner, basket_id) → BasketLock
+    NextBasketId(Address),            // owner → u64
+    ExtensionFee,                     // i128 — fee for extend_lock_paid, in the lock's token
+    RequireMemo,                      // bool — reject deposits without a memo
+    DefaultLockDuration,              // u64 — ledgers used by lock_default
+    AutoDust,                         // bool — withdraw sweeps sub-minimum remainders
+    AccruedFees(Address),             // token → i128, release fees collected to date
+    EmergencyAt,                      // u64 — ledger from which emergency_withdraw is open
+    Recovery(Address),                // owner → Address allowed to reclaim_as for them
+    Vesting(Address, u64),            // (owner, lock_id) → VestingSchedule
+    MaxInterestRate(Address),         // token → u32, highest rate lock_with_interest accepts
 }
 
+// ─── Event verbosity ────────────────────────────────────────────────────────
+
+/// Emit nothing.
+pub const EVENTS_NONE: u32 = 0;
+/// Emit only fund movements and lock lifecycle (deposit, withdraw, lock,
+/// release, reclaim).
+pub const EVENTS_KEY: u32 = 1;
+/// Emit everything, including init, config changes and lock splits.
+pub const EVENTS_VERBOSE: u32 = 2;
+
+// ─── Paging ─────────────────────────────────────────────────────────────────
+
+/// Maximum number of entries any `list_*` call returns.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Maximum number of lock ids a whole-owner scan (`next_expiry`) inspects.
+pub const MAX_SCAN: u32 = 200;
+
+// ─── Diagnostics ────────────────────────────────────────────────────────────
+
+/// Ledgers an expired lock may stay `Active` (expiry is lazy, applied on
+/// reclaim or settle) before `check_invariants` reports it (~1 day).
+pub const EXPIRY_GRACE: u64 = 17_280;
+
+// ─── Pricing ────────────────────────────────────────────────────────────────
+
+/// Fixed-point scale of `PriceOracle::price`: a price of `PRICE_SCALE`
+/// means one token unit is worth one reference unit.
+pub const PRICE_SCALE: i128 = 10_000_000;
+
+// ─── Lock metadata ──────────────────────────────────────────────────────────
+
+/// Maximum length of `LockEntry::metadata`.
+pub const MAX_METADATA_LEN: u32 = 256;
+
 // ─── Lock entry stored on-chain ─────────────────────────────────────────────
 
 #[derive(Clone, Debug, PartialEq)]
@@ -22,6 +94,13 @@ pub enum LockStatus {
     Active,
     Released,
     Expired,
+    /// Frozen by the contract owner until `resolve_dispute`.
+    Disputed,
+    /// Release offered to a recipient, waiting on `accept_release`.
+    Offered,
+    /// Reserved via `reserve_lock` but not yet funded; holds no tokens
+    /// until `fund_lock`.
+    Pending,
 }
 
 #[derive(Clone, Debug)]
@@ -31,6 +110,104 @@ pub struct LockEntry {
     pub amount: i128,
     pub expires_at: u64,
     pub status: LockStatus,
+    /// When set, a keeper may roll an expired lock forward via `renew_lock`.
+    pub auto_renew: bool,
+    /// Ledgers added per renewal period.
+    pub renew_ledgers: u64,
+    /// Opaque integrator context, at most `MAX_METADATA_LEN` bytes.
+    pub metadata: Bytes,
+    /// Counterparty the lock is held for, set via `lock_to`.
+    pub beneficiary: Option<Address>,
+    /// Ledger the lock was created at.
+    pub created_at: u64,
+    /// Simple interest paid on `release` from the token's reserve, in basis
+    /// points per 1000 ledgers. 0 for no interest.
+    pub rate_bps_per_1000_ledgers: u32,
+}
+
+/// The fields an escrow card needs, returned by `lock_summary`.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct LockSummary {
+    pub token: Address,
+    pub amount: i128,
+    pub expires_at: u64,
+    pub status: LockStatus,
+    pub beneficiary: Option<Address>,
+}
+
+/// A token's accounting in one read, returned by `token_stats`.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct TokenStats {
+    /// Sum of all available (unlocked) balances, `TotalBalance`.
+    pub total_deposited: i128,
+    /// Sum of active lock amounts, `TotalLocked`.
+    pub total_locked: i128,
+    /// Release fees collected over the vault's lifetime.
+    pub accrued_fees: i128,
+    /// The vault's actual token balance.
+    pub actual_balance: i128,
+}
+
+/// Several tokens escrowed as one deal via `lock_basket`. All items are
+/// released to `recipient` or reclaimed by the owner together.
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct BasketLock {
+    pub items: Vec<(Address, i128)>,
+    pub expires_at: u64,
+    pub recipient: Address,
+    pub status: LockStatus,
+}
+
+/// Linear unlock schedule of a `lock_vesting` lock. `total` vests in a
+/// straight line from `start_ledger` to `end_ledger`; `released` is what
+/// `release_vested` has paid out so far.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct VestingSchedule {
+    pub start_ledger: u64,
+    pub end_ledger: u64,
+    pub total: i128,
+    pub released: i128,
+}
+
+// ─── Delayed withdrawals ────────────────────────────────────────────────────
+
+/// Withdrawals above `threshold` must go through `request_withdraw` and wait
+/// `delay` ledgers before `execute_withdraw`.
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct WithdrawDelay {
+    pub threshold: i128,
+    pub delay: u64,
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct PendingWithdraw {
+    pub amount: i128,
+    pub unlock_at: u64,
+}
+
+// ─── Withdrawal rate limit ──────────────────────────────────────────────────
+
+/// Each account may withdraw at most `max_amount` of a token per `window`
+/// ledgers.
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct WithdrawLimit {
+    pub max_amount: i128,
+    pub window: u64,
+}
+
+/// Amount withdrawn in the window starting at ledger `start`.
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct WithdrawWindow {
+    pub start: u64,
+    pub amount: i128,
 }
 
 // ─── Errors ─────────────────────────────────────────────────────────────────
@@ -39,16 +216,70 @@ pub struct LockEntry {
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum VaultError {
-    AlreadyInitialized = 1,
-    NotInitialized     = 2,
-    NotOwner           = 3,
-    InsufficientFunds  = 4,
-    InvalidAmount      = 5,
-    LockNotFound       = 6,
-    LockNotActive      = 7,
-    LockExpired        = 8,
-    LockNotExpired     = 9,
-    InvalidExpiry      = 10,
+    AlreadyInitialized    = 1,
+    NotInitialized        = 2,
+    NotOwner              = 3,
+    InsufficientFunds     = 4,
+    InvalidAmount         = 5,
+    LockNotFound          = 6,
+    LockNotActive         = 7,
+    LockExpired           = 8,
+    LockNotExpired        = 9,
+    InvalidExpiry         = 10,
+    InvalidConfig         = 11,
+    NoSwapAdapter         = 12,
+    SwapFailed            = 13,
+    LockTooLong           = 14,
+    NotAutoRenew          = 15,
+    WithdrawDelayed       = 16,
+    WithdrawNotReady      = 17,
+    NoPendingWithdraw     = 18,
+    LockDisputed          = 19,
+    InsufficientAllowance = 20,
+    MetadataTooLong       = 21,
+    BelowMinDeposit       = 22,
+    CapExceeded           = 23,
+    NotRecipient          = 24,
+    RateLimited           = 25,
+    ExtensionDisabled     = 26,
+    MemoRequired          = 27,
+    TransferFailed        = 28,
+    TokenMismatch         = 29,
+    NoDefaultDuration     = 30,
+    AccountFrozen         = 31,
+    ChallengePeriod       = 32,
+    ChallengeClosed       = 33,
+    ReserveInsufficient   = 34,
+    NotEmergency          = 35,
+    NotRecovery           = 36,
+    VestingLock           = 37,
+    RateTooHigh           = 38,
+    InterestLock          = 39,
+}
+
+// ─── External interfaces ────────────────────────────────────────────────────
+
+/// DEX adapter used by `lock_swapped`. The vault transfers `amount_in` of
+/// `token_in` to the adapter before calling `swap`; the adapter must send at
+/// least `min_out` of `token_out` to `to` and return the amount sent.
+#[contractclient(name = "SwapAdapterClient")]
+pub trait SwapAdapter {
+    fn swap(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128;
+}
+
+/// Price source used by `portfolio_value`. Returns the value of one unit of
+/// `token` in the oracle's reference unit, scaled by `PRICE_SCALE`, or
+/// `None` for tokens it doesn't price.
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    fn price(env: Env, token: Address) -> Option<i128>;
 }
 
 // ─── Contract ───────────────────────────────────────────────────────────────
@@ -68,12 +299,13 @@ impl EscrowVault {
         env.storage().instance().set(&DataKey::Owner, &owner);
         // Bump instance TTL to ~30 days (ledgers ≈ 5s each)
         env.storage().instance().extend_ttl(518_400, 518_400);
-        env.events().publish((Symbol::new(&env, "init"),), owner);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "init"),), owner);
         Ok(())
     }
 
     /// Deposit `amount` of `token` into the vault on behalf of `owner`.
-    /// The caller must be `owner` (require_auth).
+    /// The caller must be `owner` (require_auth). Rejected with
+    /// `MemoRequired` while `require_memo` is set.
     pub fn deposit(
         env: Env,
         owner: Address,
@@ -82,123 +314,127 @@ impl EscrowVault {
     ) -> Result<(), VaultError> {
         Self::require_init(&env)?;
         owner.require_auth();
-        if amount <= 0 {
-            return Err(VaultError::InvalidAmount);
+        if Self::require_memo(env.clone()) {
+            return Err(VaultError::MemoRequired);
         }
+        Self::pull_deposit(&env, &owner, &token, amount)?;
 
-        // Transfer tokens from owner → this contract
-        let client = token::Client::new(&env, &token);
-        client.transfer(&owner, &env.current_contract_address(), &amount);
-
-        // Credit internal balance
-        let key = DataKey::Balance(owner.clone(), token.clone());
-        let prev: i128 = env.storage().persistent().get(&key).unwrap_or(0);
-        env.storage().persistent().set(&key, &(prev + amount));
-        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
-
-        env.events().publish(
+        Self::emit(
+            &env,
+            EVENTS_KEY,
             (Symbol::new(&env, "deposit"), owner, token),
             amount,
         );
         Ok(())
     }
 
-    /// Withdraw unlocked `amount` of `token` back to `owner`.
-    pub fn withdraw(
+    /// Like `deposit`, tagging it with `memo` (at most `MAX_METADATA_LEN`
+    /// bytes) for off-chain reconciliation. The memo is emitted with the
+    /// deposit event, not stored. An empty memo counts as none.
+    pub fn deposit_with_memo(
         env: Env,
         owner: Address,
         token: Address,
         amount: i128,
+        memo: Bytes,
     ) -> Result<(), VaultError> {
         Self::require_init(&env)?;
         owner.require_auth();
-        if amount <= 0 {
-            return Err(VaultError::InvalidAmount);
+        if memo.is_empty() && Self::require_memo(env.clone()) {
+            return Err(VaultError::MemoRequired);
         }
-
-        let key = DataKey::Balance(owner.clone(), token.clone());
-        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
-        if balance < amount {
-            return Err(VaultError::InsufficientFunds);
+        if memo.len() > MAX_METADATA_LEN {
+            return Err(VaultError::MetadataTooLong);
         }
+        Self::pull_deposit(&env, &owner, &token, amount)?;
 
-        // Transfer tokens from contract → owner
-        let client = token::Client::new(&env, &token);
-        client.transfer(&env.current_contract_address(), &owner, &amount);
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "deposit"), owner, token),
+            (amount, memo),
+        );
+        Ok(())
+    }
 
-        env.storage().persistent().set(&key, &(balance - amount));
+    /// Self-imposed kill switch for a suspected compromise: while set,
+    /// withdrawals (including `withdraw_from`), new locks and releases for
+    /// `owner` fail with `AccountFrozen`. Deposits, reclaims and reads
+    /// still work.
+    pub fn freeze_self(env: Env, owner: Address) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let key = DataKey::SelfFrozen(owner.clone());
+        env.storage().persistent().set(&key, &true);
         env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
 
-        env.events().publish(
-            (Symbol::new(&env, "withdraw"), owner, token),
-            amount,
-        );
+        Self::emit(&env, EVENTS_KEY, (Symbol::new(&env, "freeze_self"), owner), ());
         Ok(())
     }
 
-    /// Lock `amount` of `token` from `owner`'s deposited balance.
-    /// Creates an on-chain LockEntry with `expires_at` ledger sequence.
-    /// Returns the assigned lock_id.
-    pub fn lock(
-        env: Env,
-        owner: Address,
-        token: Address,
-        amount: i128,
-        expires_at: u64,
-    ) -> Result<u64, VaultError> {
+    /// Lift a `freeze_self` hold.
+    pub fn unfreeze_self(env: Env, owner: Address) -> Result<(), VaultError> {
         Self::require_init(&env)?;
         owner.require_auth();
-        if amount <= 0 {
-            return Err(VaultError::InvalidAmount);
-        }
-        let current_ledger = env.ledger().sequence() as u64;
-        if expires_at <= current_ledger {
-            return Err(VaultError::InvalidExpiry);
-        }
 
-        // Deduct from available balance
-        let bal_key = DataKey::Balance(owner.clone(), token.clone());
-        let balance: i128 = env.storage().persistent().get(&bal_key).unwrap_or(0);
-        if balance < amount {
-            return Err(VaultError::InsufficientFunds);
-        }
-        env.storage().persistent().set(&bal_key, &(balance - amount));
-        env.storage().persistent().extend_ttl(&bal_key, 518_400, 518_400);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::SelfFrozen(owner.clone()));
 
-        // Assign sequential lock_id
-        let id_key = DataKey::NextLockId(owner.clone());
-        let lock_id: u64 = env.storage().persistent().get(&id_key).unwrap_or(0);
-        env.storage().persistent().set(&id_key, &(lock_id + 1));
+        Self::emit(&env, EVENTS_KEY, (Symbol::new(&env, "unfreeze_self"), owner), ());
+        Ok(())
+    }
 
-        // Store the lock
-        let entry = LockEntry {
-            token: token.clone(),
-            amount,
-            expires_at,
-            status: LockStatus::Active,
-        };
-        let lock_key = DataKey::Lock(owner.clone(), lock_id);
-        env.storage().persistent().set(&lock_key, &entry);
-        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+    /// Designate `recovery` as able to reclaim `owner`'s expired locks via
+    /// `reclaim_as`, e.g. in case the owner's key is lost. Replaces any
+    /// previous recovery address.
+    pub fn set_recovery(env: Env, owner: Address, recovery: Address) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
 
-        env.events().publish(
-            (Symbol::new(&env, "lock"), owner, token),
-            (lock_id, amount, expires_at),
-        );
-        Ok(lock_id)
+        let key = DataKey::Recovery(owner.clone());
+        env.storage().persistent().set(&key, &recovery);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+
+        Self::emit(&env, EVENTS_KEY, (Symbol::new(&env, "set_recovery"), owner), recovery);
+        Ok(())
     }
 
-    /// Release a locked escrow to `recipient`.
-    /// Only the lock owner can release, and only while the lock is active
-    /// and not yet expired.
-    pub fn release(
+    /// Remove `owner`'s recovery address.
+    pub fn clear_recovery(env: Env, owner: Address) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Recovery(owner.clone()));
+
+        Self::emit(&env, EVENTS_KEY, (Symbol::new(&env, "clear_recovery"), owner), ());
+        Ok(())
+    }
+
+    /// Deposit `amount` of `token`, then move `add_amount` of the new
+    /// balance into active lock `lock_id`, which must hold the same token
+    /// and not be expired. Subject to the same checks as `deposit`.
+    /// Interest-bearing locks can't be topped up (`InterestLock`), since
+    /// the new funds would earn interest from the lock's creation.
+    pub fn deposit_and_extend(
         env: Env,
         owner: Address,
+        token: Address,
+        amount: i128,
         lock_id: u64,
-        recipient: Address,
+        add_amount: i128,
     ) -> Result<(), VaultError> {
         Self::require_init(&env)?;
         owner.require_auth();
+        if Self::require_memo(env.clone()) {
+            return Err(VaultError::MemoRequired);
+        }
+        if add_amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
 
         let lock_key = DataKey::Lock(owner.clone(), lock_id);
         let mut entry: LockEntry = env
@@ -206,224 +442,5532 @@ impl EscrowVault {
             .persistent()
             .get(&lock_key)
             .ok_or(VaultError::LockNotFound)?;
-
         if entry.status != LockStatus::Active {
             return Err(VaultError::LockNotActive);
         }
-        let current_ledger = env.ledger().sequence() as u64;
-        if current_ledger > entry.expires_at {
-            // Mark expired so future calls see the right status
-            entry.status = LockStatus::Expired;
-            env.storage().persistent().set(&lock_key, &entry);
+        if env.ledger().sequence() as u64 > entry.expires_at {
             return Err(VaultError::LockExpired);
         }
+        if entry.token != token {
+            return Err(VaultError::TokenMismatch);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+        if entry.rate_bps_per_1000_ledgers > 0 {
+            return Err(VaultError::InterestLock);
+        }
 
-        // Transfer tokens from contract → recipient
-        let client = token::Client::new(&env, &entry.token);
-        client.transfer(
-            &env.current_contract_address(),
-            &recipient,
-            &entry.amount,
+        Self::pull_deposit(&env, &owner, &token, amount)?;
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "deposit"), owner.clone(), token.clone()),
+            amount,
         );
 
-        entry.status = LockStatus::Released;
+        Self::debit(&env, &owner, &token, add_amount)?;
+        Self::add_locked(&env, &token, add_amount);
+        entry.amount += add_amount;
         env.storage().persistent().set(&lock_key, &entry);
         env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
 
-        env.events().publish(
-            (Symbol::new(&env, "release"), owner),
-            (lock_id, recipient, entry.amount),
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "top_up"), owner),
+            (lock_id, add_amount, entry.amount),
         );
         Ok(())
     }
 
-    /// Reclaim funds from an expired lock back to the owner's balance.
-    /// Anyone can call this, but funds return to the original lock owner.
-    pub fn reclaim(
+    /// Withdraw unlocked `amount` of `token` back to `owner`.
+    pub fn withdraw(
         env: Env,
         owner: Address,
-        lock_id: u64,
+        token: Address,
+        amount: i128,
     ) -> Result<(), VaultError> {
         Self::require_init(&env)?;
         owner.require_auth();
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
 
-        let lock_key = DataKey::Lock(owner.clone(), lock_id);
-        let mut entry: LockEntry = env
-            .storage()
-            .persistent()
-            .get(&lock_key)
-            .ok_or(VaultError::LockNotFound)?;
+        // With auto-dust on, a remainder below the token's minimum deposit
+        // goes out with this withdrawal and the balance entry is dropped.
+        let balance = Self::balance(env.clone(), owner.clone(), token.clone());
+        let remainder = balance - amount;
+        let sweep = Self::auto_dust(env.clone())
+            && remainder >= 0
+            && remainder < Self::min_deposit(env.clone(), token.clone());
+        let sent = if sweep { balance } else { amount };
 
-        if entry.status != LockStatus::Active {
-            return Err(VaultError::LockNotActive);
+        // The delay and rate limit apply to what actually leaves the vault
+        if let Some(cfg) = Self::withdraw_delay(env.clone()) {
+            if sent > cfg.threshold {
+                return Err(VaultError::WithdrawDelayed);
+            }
         }
-        let current_ledger = env.ledger().sequence() as u64;
-        if current_ledger <= entry.expires_at {
-            return Err(VaultError::LockNotExpired);
+        Self::send_from_balance(&env, &owner, &token, sent, &owner)?;
+        if sweep {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Balance(owner, token));
+        }
+        Ok(())
+    }
+
+    /// Wind-down escape hatch: once the ledger set by `set_emergency_at` is
+    /// reached, return `owner`'s whole `token` balance plus every active or
+    /// offered lock in that token (the last `MAX_SCAN` ids), ignoring
+    /// expiry, beneficiaries and withdraw delays. `freeze_self` and the
+    /// withdrawal rate limit still apply. This breaks the escrow guarantee
+    /// and exists only for shutting the vault down.
+    /// Disputed locks are left to `resolve_dispute` and vesting locks to
+    /// their beneficiary's `release_vested`. Returns the amount sent.
+    pub fn emergency_withdraw(
+        env: Env,
+        owner: Address,
+        token: Address,
+    ) -> Result<i128, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        let open = Self::emergency_at(env.clone())
+            .is_some_and(|at| env.ledger().sequence() as u64 >= at);
+        if !open {
+            return Err(VaultError::NotEmergency);
         }
 
-        // Return to owner's balance
-        let bal_key = DataKey::Balance(owner.clone(), entry.token.clone());
-        let balance: i128 = env.storage().persistent().get(&bal_key).unwrap_or(0);
-        env.storage().persistent().set(&bal_key, &(balance + entry.amount));
+        let next = Self::lock_count(env.clone(), owner.clone());
+        for lock_id in next.saturating_sub(MAX_SCAN as u64)..next {
+            let lock_key = DataKey::Lock(owner.clone(), lock_id);
+            let entry: Option<LockEntry> = env.storage().persistent().get(&lock_key);
+            let Some(mut entry) = entry else { continue };
+            if entry.token != token
+                || !matches!(entry.status, LockStatus::Active | LockStatus::Offered)
+                || Self::is_vesting(&env, &owner, lock_id)
+            {
+                continue;
+            }
 
-        entry.status = LockStatus::Expired;
-        env.storage().persistent().set(&lock_key, &entry);
+            Self::add_locked(&env, &token, -entry.amount);
+            Self::credit(&env, &owner, &token, entry.amount);
+            entry.status = LockStatus::Expired;
+            env.storage().persistent().set(&lock_key, &entry);
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Offer(owner.clone(), lock_id));
+            Self::unindex_incoming(&env, &owner, lock_id, &entry);
+        }
 
-        env.events().publish(
-            (Symbol::new(&env, "reclaim"), owner),
-            (lock_id, entry.amount),
+        let amount = Self::balance(env.clone(), owner.clone(), token.clone());
+        if amount > 0 {
+            Self::send_from_balance(&env, &owner, &token, amount, &owner)?;
+        }
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "emergency_withdraw"), owner, token),
+            amount,
         );
-        Ok(())
+        Ok(amount)
     }
 
-    // ─── Read-only queries ──────────────────────────────────────────────
-
-    /// Get the deposited (unlocked) balance for an owner+token pair.
-    pub fn balance(env: Env, owner: Address, token: Address) -> i128 {
-        let key = DataKey::Balance(owner, token);
-        env.storage().persistent().get(&key).unwrap_or(0)
-    }
+    /// Start a delayed withdrawal of `amount`. Required for amounts above
+    /// the configured threshold. Funds stay in the available balance until
+    /// `execute_withdraw`; a new request replaces any pending one.
+    /// Returns the ledger at which it can be executed.
+    pub fn request_withdraw(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if Self::balance(env.clone(), owner.clone(), token.clone()) < amount {
+            return Err(VaultError::InsufficientFunds);
+        }
 
-    /// Get a specific lock entry.
-    pub fn get_lock(env: Env, owner: Address, lock_id: u64) -> Result<LockEntry, VaultError> {
-        let key = DataKey::Lock(owner, lock_id);
+        let delay = Self::withdraw_delay(env.clone()).map_or(0, |cfg| cfg.delay);
+        let unlock_at = env.ledger().sequence() as u64 + delay;
+        let key = DataKey::PendingWithdraw(owner.clone(), token.clone());
         env.storage()
             .persistent()
-            .get(&key)
-            .ok_or(VaultError::LockNotFound)
-    }
+            .set(&key, &PendingWithdraw { amount, unlock_at });
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
 
-    /// Get the contract owner.
-    pub fn owner(env: Env) -> Result<Address, VaultError> {
-        env.storage()
-            .instance()
-            .get(&DataKey::Owner)
-            .ok_or(VaultError::NotInitialized)
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "withdraw_request"), owner, token),
+            (amount, unlock_at),
+        );
+        Ok(unlock_at)
     }
 
-    // ─── Internal ───────────────────────────────────────────────────────
+    /// Complete a pending delayed withdrawal once its delay has passed.
+    /// Returns the amount withdrawn.
+    pub fn execute_withdraw(env: Env, owner: Address, token: Address) -> Result<i128, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
 
-    fn require_init(env: &Env) -> Result<(), VaultError> {
-        if !env.storage().instance().has(&DataKey::Owner) {
-            return Err(VaultError::NotInitialized);
+        let key = DataKey::PendingWithdraw(owner.clone(), token.clone());
+        let pending: PendingWithdraw = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(VaultError::NoPendingWithdraw)?;
+        if (env.ledger().sequence() as u64) < pending.unlock_at {
+            return Err(VaultError::WithdrawNotReady);
         }
-        env.storage().instance().extend_ttl(518_400, 518_400);
-        Ok(())
+
+        env.storage().persistent().remove(&key);
+        Self::send_from_balance(&env, &owner, &token, pending.amount, &owner)?;
+        Ok(pending.amount)
     }
-}
 
-// ─── Tests ──────────────────────────────────────────────────────────────────
+    /// Abort a pending delayed withdrawal, e.g. one made with a stolen key.
+    pub fn cancel_withdraw(env: Env, owner: Address, token: Address) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
 
-#[cfg(test)]
+        let key = DataKey::PendingWithdraw(owner.clone(), token.clone());
+        let pending: PendingWithdraw = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(VaultError::NoPendingWithdraw)?;
+        env.storage().persistent().remove(&key);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "withdraw_cancel"), owner, token),
+            pending.amount,
+        );
+        Ok(())
+    }
+
+    /// Let `spender` withdraw up to `amount` of `token` from the owner's
+    /// available balance. Replaces any existing allowance; 0 revokes it.
+    pub fn approve(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        if amount < 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        let key = DataKey::Allowance(owner.clone(), spender.clone(), token.clone());
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &amount);
+            env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        }
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "approve"), owner, spender),
+            (token, amount),
+        );
+        Ok(())
+    }
+
+    /// Withdraw from `owner`'s available balance to `spender`, consuming the
+    /// allowance. Amounts above the withdraw-delay threshold are rejected;
+    /// delayed withdrawals are owner-only.
+    pub fn withdraw_from(
+        env: Env,
+        spender: Address,
+        owner: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        spender.require_auth();
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if let Some(cfg) = Self::withdraw_delay(env.clone()) {
+            if amount > cfg.threshold {
+                return Err(VaultError::WithdrawDelayed);
+            }
+        }
+
+        let key = DataKey::Allowance(owner.clone(), spender.clone(), token.clone());
+        let allowed: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if amount > allowed {
+            return Err(VaultError::InsufficientAllowance);
+        }
+        if allowed == amount {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &(allowed - amount));
+        }
+
+        Self::send_from_balance(&env, &owner, &token, amount, &spender)
+    }
+
+    /// Lock `amount` of `token` from `owner`'s deposited balance.
+    /// Creates an on-chain LockEntry with `expires_at` ledger sequence.
+    /// Returns the assigned lock_id.
+    pub fn lock(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        // Deduct from available balance
+        Self::debit(&env, &owner, &token, amount)?;
+
+        let entry = Self::active_entry(&env, token, amount, expires_at);
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Like `lock`, attaching `metadata` (at most `MAX_METADATA_LEN` bytes)
+    /// to the entry. Returns the assigned lock_id.
+    pub fn lock_with_metadata(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+        metadata: Bytes,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if metadata.len() > MAX_METADATA_LEN {
+            return Err(VaultError::MetadataTooLong);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        Self::debit(&env, &owner, &token, amount)?;
+
+        let mut entry = Self::active_entry(&env, token, amount, expires_at);
+        entry.metadata = metadata;
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Like `lock`, accruing simple interest of `rate_bps_per_1000_ledgers`
+    /// basis points per 1000 ledgers until release. The rate may not exceed
+    /// the token's `max_interest_rate` (`RateTooHigh`). Interest comes from
+    /// the token's reserve (see `fund_reserve`) and is paid only when the
+    /// funds go to someone other than the owner, via `release`,
+    /// `accept_release`, `settle` or `resolve_dispute(Some)`; closing then
+    /// fails with `ReserveInsufficient` if the reserve can't cover it. The
+    /// owner gets no interest back on `reclaim`, `resolve_dispute(None)` or
+    /// `emergency_withdraw`, and the lock can't be topped up via
+    /// `deposit_and_extend` (`InterestLock`). Returns the assigned lock_id.
+    pub fn lock_with_interest(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+        rate_bps_per_1000_ledgers: u32,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+        if rate_bps_per_1000_ledgers > Self::max_interest_rate(env.clone(), token.clone()) {
+            return Err(VaultError::RateTooHigh);
+        }
+
+        Self::debit(&env, &owner, &token, amount)?;
+
+        let mut entry = Self::active_entry(&env, token, amount, expires_at);
+        entry.rate_bps_per_1000_ledgers = rate_bps_per_1000_ledgers;
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Like `lock`, naming `beneficiary` as the counterparty the funds are
+    /// held for. The lock shows up in `incoming_locks(beneficiary)` while
+    /// active. Returns the assigned lock_id.
+    pub fn lock_to(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+        beneficiary: Address,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        Self::debit(&env, &owner, &token, amount)?;
+
+        let mut entry = Self::active_entry(&env, token, amount, expires_at);
+        entry.beneficiary = Some(beneficiary);
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Reserve a lock id for `amount` of `token` without touching the
+    /// owner's balance, e.g. to quote a deal before the funds arrive. The
+    /// lock is `Pending` until `fund_lock`; until then it can be dropped
+    /// with `cancel_reservation`. Returns the assigned lock_id.
+    pub fn reserve_lock(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        let mut entry = Self::active_entry(&env, token.clone(), amount, expires_at);
+        entry.status = LockStatus::Pending;
+        let lock_id = Self::next_lock_id(&env, &owner);
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "reserve_lock"), owner, token),
+            (lock_id, amount, expires_at),
+        );
+        Ok(lock_id)
+    }
+
+    /// Fund a `Pending` reservation from the owner's balance, making it an
+    /// ordinary `Active` lock. Fails with `InvalidExpiry` if the reserved
+    /// expiry has already passed.
+    pub fn fund_lock(env: Env, owner: Address, lock_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+        if entry.status != LockStatus::Pending {
+            return Err(VaultError::LockNotActive);
+        }
+        Self::check_expiry(&env, entry.expires_at)?;
+
+        Self::debit(&env, &owner, &entry.token, entry.amount)?;
+        Self::add_locked(&env, &entry.token, entry.amount);
+
+        entry.status = LockStatus::Active;
+        entry.created_at = env.ledger().sequence() as u64;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "fund_lock"), owner, entry.token),
+            (lock_id, entry.amount),
+        );
+        Ok(())
+    }
+
+    /// Drop an unfunded `Pending` reservation. Nothing is refunded because
+    /// nothing was taken; the lock id is not reused.
+    pub fn cancel_reservation(env: Env, owner: Address, lock_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+        if entry.status != LockStatus::Pending {
+            return Err(VaultError::LockNotActive);
+        }
+        env.storage().persistent().remove(&lock_key);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "cancel_reservation"), owner),
+            lock_id,
+        );
+        Ok(())
+    }
+
+    /// Like `lock_to`, but `amount` unlocks for `beneficiary` linearly
+    /// between `start_ledger` and `end_ledger` (the lock's expiry), claimed
+    /// with `release_vested`. The owner can't release, offer, reclaim,
+    /// split, top up, transfer or re-token it (`VestingLock`). Returns the
+    /// assigned lock_id.
+    pub fn lock_vesting(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        start_ledger: u64,
+        end_ledger: u64,
+        beneficiary: Address,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if start_ledger >= end_ledger {
+            return Err(VaultError::InvalidExpiry);
+        }
+        Self::check_expiry(&env, end_ledger)?;
+
+        Self::debit(&env, &owner, &token, amount)?;
+
+        let mut entry = Self::active_entry(&env, token, amount, end_ledger);
+        entry.beneficiary = Some(beneficiary);
+        let lock_id = Self::create_lock(&env, &owner, entry);
+
+        let key = DataKey::Vesting(owner, lock_id);
+        let schedule = VestingSchedule { start_ledger, end_ledger, total: amount, released: 0 };
+        env.storage().persistent().set(&key, &schedule);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Ok(lock_id)
+    }
+
+    /// Like `lock_to`, expiring `default_lock_duration` ledgers from now.
+    /// Fails with `NoDefaultDuration` if no default is configured.
+    pub fn lock_default(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        recipient: Address,
+    ) -> Result<u64, VaultError> {
+        let duration =
+            Self::default_lock_duration(env.clone()).ok_or(VaultError::NoDefaultDuration)?;
+        let expires_at = env.ledger().sequence() as u64 + duration;
+        Self::lock_to(env, owner, token, amount, expires_at, recipient)
+    }
+
+    /// Lock tokens pulled straight from `owner`'s wallet via a token
+    /// allowance granted to this contract, skipping `deposit`. The available
+    /// balance is untouched. `recipient` becomes the lock's beneficiary.
+    /// Returns the assigned lock_id.
+    pub fn lock_from_allowance(
+        env: Env,
+        owner: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+        recipient: Address,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        let contract = env.current_contract_address();
+        let client = token::Client::new(&env, &token);
+        client.transfer_from(&contract, &owner, &contract, &amount);
+
+        let mut entry = Self::active_entry(&env, token, amount, expires_at);
+        entry.beneficiary = Some(recipient);
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Lock several tokens from `owner`'s balance as a single escrow for
+    /// `recipient`. Each `(token, amount)` item is debited; if any item is
+    /// invalid or underfunded nothing is locked. Basket ids are numbered
+    /// separately from lock ids. Returns the assigned basket_id.
+    pub fn lock_basket(
+        env: Env,
+        owner: Address,
+        items: Vec<(Address, i128)>,
+        expires_at: u64,
+        recipient: Address,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if items.is_empty() {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+
+        for (token, amount) in items.iter() {
+            if amount <= 0 {
+                return Err(VaultError::InvalidAmount);
+            }
+            Self::debit(&env, &owner, &token, amount)?;
+            Self::add_locked(&env, &token, amount);
+        }
+
+        let id_key = DataKey::NextBasketId(owner.clone());
+        let basket_id: u64 = env.storage().persistent().get(&id_key).unwrap_or(0);
+        env.storage().persistent().set(&id_key, &(basket_id + 1));
+
+        let basket = BasketLock {
+            items,
+            expires_at,
+            recipient,
+            status: LockStatus::Active,
+        };
+        let key = DataKey::Basket(owner.clone(), basket_id);
+        env.storage().persistent().set(&key, &basket);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "lock_basket"), owner),
+            (basket_id, basket.items.len(), expires_at),
+        );
+        Ok(basket_id)
+    }
+
+    /// Swap `amount_in` of deposited `token_in` into `token_out` through the
+    /// configured swap adapter, then lock the proceeds until `expires_at`.
+    /// Reverts if the adapter fails or delivers less than `min_out`.
+    /// Returns the assigned lock_id.
+    pub fn lock_swapped(
+        env: Env,
+        owner: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        expires_at: u64,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+        if amount_in <= 0 || min_out < 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::check_expiry(&env, expires_at)?;
+        let adapter: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapAdapter)
+            .ok_or(VaultError::NoSwapAdapter)?;
+
+        // Deduct token_in from available balance
+        Self::debit(&env, &owner, &token_in, amount_in)?;
+
+        // Hand token_in to the adapter and measure what actually comes back
+        let vault = env.current_contract_address();
+        let out_client = token::Client::new(&env, &token_out);
+        let before = out_client.balance(&vault);
+        token::Client::new(&env, &token_in).transfer(&vault, &adapter, &amount_in);
+
+        let swapped = SwapAdapterClient::new(&env, &adapter).try_swap(
+            &token_in,
+            &token_out,
+            &amount_in,
+            &min_out,
+            &vault,
+        );
+        if !matches!(swapped, Ok(Ok(_))) {
+            return Err(VaultError::SwapFailed);
+        }
+        let amount_out = out_client.balance(&vault) - before;
+        if amount_out <= 0 || amount_out < min_out {
+            return Err(VaultError::SwapFailed);
+        }
+
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "swap"), owner.clone(), token_in),
+            (token_out.clone(), amount_in, amount_out),
+        );
+
+        let entry = Self::active_entry(&env, token_out, amount_out, expires_at);
+        Ok(Self::create_lock(&env, &owner, entry))
+    }
+
+    /// Deposit native XLM via its Stellar Asset Contract.
+    pub fn deposit_native(env: Env, owner: Address, amount: i128) -> Result<(), VaultError> {
+        let native = Self::native_token(env.clone());
+        Self::deposit(env, owner, native, amount)
+    }
+
+    /// Lock deposited native XLM until `expires_at`. Returns the lock_id.
+    pub fn lock_native(
+        env: Env,
+        owner: Address,
+        amount: i128,
+        expires_at: u64,
+    ) -> Result<u64, VaultError> {
+        let native = Self::native_token(env.clone());
+        Self::lock(env, owner, native, amount, expires_at)
+    }
+
+    /// Split `amount` off an active lock into a new lock with the same
+    /// token and expiry. The original keeps the remainder.
+    /// Returns the new lock_id.
+    pub fn split_lock(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        amount: i128,
+    ) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if amount <= 0 || amount >= entry.amount {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+
+        let new_id = Self::next_lock_id(&env, &owner);
+        let mut split = entry.clone();
+        split.amount = amount;
+        let split_key = DataKey::Lock(owner.clone(), new_id);
+        env.storage().persistent().set(&split_key, &split);
+        env.storage().persistent().extend_ttl(&split_key, 518_400, 518_400);
+        Self::index_incoming(&env, &owner, new_id, &split);
+
+        entry.amount -= amount;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "split"), owner),
+            (lock_id, new_id, amount),
+        );
+        Ok(new_id)
+    }
+
+    /// Fix a lock created against the wrong token: return its amount to the
+    /// owner's balance of the old token and lock the same amount of
+    /// `correct_token` from their available balance instead. The lock must
+    /// still be active; expiry and other fields are kept.
+    pub fn recorrect_lock_token(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        correct_token: Address,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status == LockStatus::Disputed {
+            return Err(VaultError::LockDisputed);
+        }
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if entry.token == correct_token {
+            return Err(VaultError::InvalidConfig);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+
+        Self::debit(&env, &owner, &correct_token, entry.amount)?;
+        Self::add_locked(&env, &correct_token, entry.amount);
+        Self::add_locked(&env, &entry.token, -entry.amount);
+        Self::credit(&env, &owner, &entry.token, entry.amount);
+
+        let wrong_token = entry.token;
+        entry.token = correct_token.clone();
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "recorrect"), owner),
+            (lock_id, wrong_token, correct_token, entry.amount),
+        );
+        Ok(())
+    }
+
+    /// Move an active lock to `new_owner`, who then controls its release
+    /// and reclaim. The lock gets a fresh id in the new owner's namespace
+    /// (reported in the `transfer_lock` event); the old id is removed.
+    pub fn transfer_lock(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        new_owner: Address,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+
+        let new_id = Self::next_lock_id(&env, &new_owner);
+        let new_key = DataKey::Lock(new_owner.clone(), new_id);
+        env.storage().persistent().set(&new_key, &entry);
+        env.storage().persistent().extend_ttl(&new_key, 518_400, 518_400);
+        env.storage().persistent().remove(&lock_key);
+
+        Self::unindex_incoming(&env, &owner, lock_id, &entry);
+        Self::index_incoming(&env, &new_owner, new_id, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "transfer_lock"), owner, new_owner),
+            (lock_id, new_id),
+        );
+        Ok(())
+    }
+
+    /// Extend the storage TTL of each listed lock to `ledgers` (clamped to
+    /// the network maximum), skipping ids that don't exist. Returns how many
+    /// entries were bumped.
+    pub fn bump_locks(
+        env: Env,
+        owner: Address,
+        lock_ids: Vec<u64>,
+        ledgers: u32,
+    ) -> Result<u32, VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let ledgers = ledgers.min(env.storage().max_ttl());
+        let mut bumped = 0;
+        for lock_id in lock_ids.iter() {
+            let key = DataKey::Lock(owner.clone(), lock_id);
+            if env.storage().persistent().has(&key) {
+                env.storage().persistent().extend_ttl(&key, ledgers, ledgers);
+                bumped += 1;
+            }
+        }
+        Ok(bumped)
+    }
+
+    /// Enable auto-renew on an active lock, rolling it forward by
+    /// `renew_ledgers` per period once expired. Passing 0 disables it.
+    pub fn set_auto_renew(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        renew_ledgers: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+
+        entry.auto_renew = renew_ledgers > 0;
+        entry.renew_ledgers = renew_ledgers;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "auto_renew"), owner),
+            (lock_id, renew_ledgers),
+        );
+        Ok(())
+    }
+
+    /// Renew an expired auto-renew lock. Anyone (e.g. a keeper) can call
+    /// this. `expires_at` advances by whole `renew_ledgers` periods until it
+    /// is in the future again, and the lock stays `Active`.
+    pub fn renew_lock(env: Env, owner: Address, lock_id: u64) -> Result<u64, VaultError> {
+        Self::require_init(&env)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if !entry.auto_renew {
+            return Err(VaultError::NotAutoRenew);
+        }
+        let current_ledger = env.ledger().sequence() as u64;
+        if current_ledger <= entry.expires_at {
+            return Err(VaultError::LockNotExpired);
+        }
+
+        let periods = (current_ledger - entry.expires_at) / entry.renew_ledgers + 1;
+        entry.expires_at += periods * entry.renew_ledgers;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "renew"), owner),
+            (lock_id, entry.expires_at),
+        );
+        Ok(entry.expires_at)
+    }
+
+    /// Push an active lock's expiry out to `new_expires_at` on behalf of
+    /// its owner. `payer` pays the configured extension fee in the lock's
+    /// token, pulled via a token allowance granted to this contract, and
+    /// the fee is credited to the lock owner's balance. Fails with
+    /// `ExtensionDisabled` if no fee is configured.
+    pub fn extend_lock_paid(
+        env: Env,
+        payer: Address,
+        owner: Address,
+        lock_id: u64,
+        new_expires_at: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        payer.require_auth();
+
+        let fee = Self::extension_fee(env.clone()).ok_or(VaultError::ExtensionDisabled)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if env.ledger().sequence() as u64 > entry.expires_at {
+            return Err(VaultError::LockExpired);
+        }
+        if new_expires_at <= entry.expires_at {
+            return Err(VaultError::InvalidExpiry);
+        }
+        Self::check_expiry(&env, new_expires_at)?;
+
+        let contract = env.current_contract_address();
+        token::Client::new(&env, &entry.token).transfer_from(&contract, &payer, &contract, &fee);
+        Self::credit(&env, &owner, &entry.token, fee);
+
+        entry.expires_at = new_expires_at;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "extend_paid"), owner),
+            (lock_id, new_expires_at, payer, fee),
+        );
+        Ok(())
+    }
+
+    /// Release a locked escrow to `recipient`.
+    /// Only the lock owner can release, and only while the lock is active
+    /// and not yet expired.
+    pub fn release(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        recipient: Address,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status == LockStatus::Disputed {
+            return Err(VaultError::LockDisputed);
+        }
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        let current_ledger = env.ledger().sequence() as u64;
+        if current_ledger > entry.expires_at {
+            // Mark expired so future calls see the right status
+            entry.status = LockStatus::Expired;
+            env.storage().persistent().set(&lock_key, &entry);
+            return Err(VaultError::LockExpired);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+
+        // Transfer tokens from contract → recipient, minus the release fee
+        Self::release_funds(&env, &owner, &entry, &recipient)?;
+
+        entry.status = LockStatus::Released;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        Self::unindex_incoming(&env, &owner, lock_id, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "release"), owner),
+            (lock_id, recipient, entry.amount),
+        );
+        Ok(())
+    }
+
+    /// Pay the beneficiary of a `lock_vesting` lock everything unlocked so
+    /// far and not yet released, minus the release fee. The lock closes as
+    /// `Released` once fully paid. Beneficiary only. Returns the gross
+    /// amount released.
+    pub fn release_vested(env: Env, owner: Address, lock_id: u64) -> Result<i128, VaultError> {
+        Self::require_init(&env)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+        let vesting_key = DataKey::Vesting(owner.clone(), lock_id);
+        let mut schedule: VestingSchedule = env
+            .storage()
+            .persistent()
+            .get(&vesting_key)
+            .ok_or(VaultError::LockNotFound)?;
+        let beneficiary = entry.beneficiary.clone().ok_or(VaultError::NotRecipient)?;
+        beneficiary.require_auth();
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+
+        let amount = Self::vested_amount(&env, &entry, &schedule);
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        Self::add_locked(&env, &entry.token, -amount);
+        Self::pay_out(&env, &entry.token, &beneficiary, amount)?;
+
+        schedule.released += amount;
+        entry.amount -= amount;
+        if entry.amount == 0 {
+            entry.status = LockStatus::Released;
+            Self::unindex_incoming(&env, &owner, lock_id, &entry);
+        }
+        env.storage().persistent().set(&vesting_key, &schedule);
+        env.storage().persistent().set(&lock_key, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "release_vested"), owner),
+            (lock_id, amount),
+        );
+        Ok(amount)
+    }
+
+    /// Release every item of an active, unexpired basket to its recipient,
+    /// each minus that token's release fee. Only the basket owner can
+    /// release.
+    pub fn release_basket(env: Env, owner: Address, basket_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+
+        let key = DataKey::Basket(owner.clone(), basket_id);
+        let mut basket: BasketLock = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(VaultError::LockNotFound)?;
+        if basket.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if env.ledger().sequence() as u64 > basket.expires_at {
+            return Err(VaultError::LockExpired);
+        }
+
+        for (token, amount) in basket.items.iter() {
+            Self::add_locked(&env, &token, -amount);
+            Self::pay_out(&env, &token, &basket.recipient, amount)?;
+        }
+
+        basket.status = LockStatus::Released;
+        env.storage().persistent().set(&key, &basket);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "release_basket"), owner),
+            (basket_id, basket.recipient),
+        );
+        Ok(())
+    }
+
+    /// First step of a two-step release: mark the lock `Offered` to
+    /// `recipient`, who must `accept_release` to receive the funds. Must be
+    /// called before expiry. Until accepted, the owner can `rescind_offer`.
+    pub fn offer_release(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        recipient: Address,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+        Self::require_not_frozen(&env, &owner)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status == LockStatus::Disputed {
+            return Err(VaultError::LockDisputed);
+        }
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if env.ledger().sequence() as u64 > entry.expires_at {
+            return Err(VaultError::LockExpired);
+        }
+        Self::require_not_vesting(&env, &owner, lock_id)?;
+
+        entry.status = LockStatus::Offered;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        let offer_key = DataKey::Offer(owner.clone(), lock_id);
+        env.storage().persistent().set(&offer_key, &recipient);
+        env.storage().persistent().extend_ttl(&offer_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "offer"), owner),
+            (lock_id, recipient),
+        );
+        Ok(())
+    }
+
+    /// Second step of a two-step release: the offered recipient takes the
+    /// funds (minus the release fee). Recipient auth required.
+    pub fn accept_release(
+        env: Env,
+        recipient: Address,
+        owner: Address,
+        lock_id: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        recipient.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Offered {
+            return Err(VaultError::LockNotActive);
+        }
+        let offer_key = DataKey::Offer(owner.clone(), lock_id);
+        let offered_to: Address = env
+            .storage()
+            .persistent()
+            .get(&offer_key)
+            .ok_or(VaultError::LockNotActive)?;
+        if offered_to != recipient {
+            return Err(VaultError::NotRecipient);
+        }
+
+        Self::release_funds(&env, &owner, &entry, &recipient)?;
+
+        entry.status = LockStatus::Released;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        env.storage().persistent().remove(&offer_key);
+        Self::unindex_incoming(&env, &owner, lock_id, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "release"), owner),
+            (lock_id, recipient, entry.amount),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a pending release offer, returning the lock to `Active`.
+    pub fn rescind_offer(env: Env, owner: Address, lock_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Offered {
+            return Err(VaultError::LockNotActive);
+        }
+
+        entry.status = LockStatus::Active;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Offer(owner.clone(), lock_id));
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "rescind"), owner),
+            lock_id,
+        );
+        Ok(())
+    }
+
+    /// Reclaim funds from an expired lock back to the owner's balance.
+    /// Anyone can call this, but funds return to the original lock owner.
+    pub fn reclaim(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        Self::reclaim_lock(&env, &owner, lock_id)?;
+        Ok(())
+    }
+
+    /// `reclaim` authorized by `caller`, which may be the owner or the
+    /// owner's recovery address (see `set_recovery`). Funds still return to
+    /// the owner's balance. Fails with `NotRecovery` for anyone else.
+    pub fn reclaim_as(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        lock_id: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        if caller != owner && Self::recovery(env.clone(), owner.clone()) != Some(caller.clone()) {
+            return Err(VaultError::NotRecovery);
+        }
+        caller.require_auth();
+
+        Self::reclaim_lock(&env, &owner, lock_id)?;
+        Ok(())
+    }
+
+    /// Return every item of an expired basket to the owner's balance.
+    pub fn reclaim_basket(env: Env, owner: Address, basket_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        owner.require_auth();
+
+        let key = DataKey::Basket(owner.clone(), basket_id);
+        let mut basket: BasketLock = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(VaultError::LockNotFound)?;
+        if basket.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if env.ledger().sequence() as u64 <= basket.expires_at {
+            return Err(VaultError::LockNotExpired);
+        }
+
+        for (token, amount) in basket.items.iter() {
+            Self::add_locked(&env, &token, -amount);
+            Self::credit(&env, &owner, &token, amount);
+        }
+
+        basket.status = LockStatus::Expired;
+        env.storage().persistent().set(&key, &basket);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "reclaim_basket"), owner),
+            basket_id,
+        );
+        Ok(())
+    }
+
+    /// Settle an expired lock on the owner's behalf. Funds go to the lock's
+    /// beneficiary (minus the release fee) if it has one, otherwise back to
+    /// the owner's balance as with `reclaim`. Anyone can call this;
+    /// `keeper` is recorded in the `settle` event.
+    pub fn settle(
+        env: Env,
+        keeper: Address,
+        owner: Address,
+        lock_id: u64,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        keeper.require_auth();
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        let beneficiary = match entry.beneficiary.clone() {
+            Some(beneficiary) => beneficiary,
+            None => {
+                let amount = Self::reclaim_lock(&env, &owner, lock_id)?;
+                Self::emit(
+                    &env,
+                    EVENTS_KEY,
+                    (Symbol::new(&env, "settle"), owner.clone()),
+                    (lock_id, owner, amount, keeper),
+                );
+                return Ok(());
+            }
+        };
+
+        if entry.status == LockStatus::Disputed {
+            return Err(VaultError::LockDisputed);
+        }
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        if env.ledger().sequence() as u64 <= entry.expires_at {
+            return Err(VaultError::LockNotExpired);
+        }
+
+        Self::release_funds(&env, &owner, &entry, &beneficiary)?;
+
+        entry.status = LockStatus::Released;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        Self::unindex_incoming(&env, &owner, lock_id, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "settle"), owner),
+            (lock_id, beneficiary, entry.amount, keeper),
+        );
+        Ok(())
+    }
+
+    /// Reclaim every eligible expired lock in `lock_ids` back to `owner`'s
+    /// balance, skipping any that are missing, closed, disputed or not yet
+    /// expired. Anyone can call this. Returns the total amount returned.
+    pub fn reclaim_batch(
+        env: Env,
+        owner: Address,
+        lock_ids: Vec<u64>,
+    ) -> Result<i128, VaultError> {
+        Self::require_init(&env)?;
+
+        let mut total: i128 = 0;
+        for lock_id in lock_ids.iter() {
+            if let Ok(amount) = Self::reclaim_lock(&env, &owner, lock_id) {
+                total += amount;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Add `amount` of `token` from `funder` to the reserve that pays
+    /// `lock_with_interest` interest. Anyone can fund it.
+    pub fn fund_reserve(
+        env: Env,
+        funder: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        let client = token::Client::new(&env, &token);
+        client.transfer(&funder, &env.current_contract_address(), &amount);
+        Self::add_total(&env, DataKey::Reserve(token.clone()), amount);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "fund_reserve"), funder, token),
+            amount,
+        );
+        Ok(())
+    }
+
+    /// Send tokens that were transferred straight to the contract (bypassing
+    /// `deposit`) to `to`. Only the surplus over the accounted total moves.
+    /// Returns the amount swept, 0 if there is no surplus. Owner only.
+    pub fn sweep_unaccounted(env: Env, token: Address, to: Address) -> Result<i128, VaultError> {
+        Self::require_owner(&env)?;
+
+        let (held, accounted) = Self::solvency(env.clone(), token.clone());
+        let surplus = held - accounted;
+        if surplus <= 0 {
+            return Ok(0);
+        }
+
+        let client = token::Client::new(&env, &token);
+        client.transfer(&env.current_contract_address(), &to, &surplus);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "sweep"), token),
+            (to, surplus),
+        );
+        Ok(surplus)
+    }
+
+    // ─── Disputes ───────────────────────────────────────────────────────
+
+    /// Freeze an active lock so neither `release` nor `reclaim` can move it
+    /// until `resolve_dispute`. Expiry does not apply while disputed.
+    /// Contract owner only.
+    pub fn dispute_lock(env: Env, owner: Address, lock_id: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+
+        entry.status = LockStatus::Disputed;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "dispute"), owner),
+            lock_id,
+        );
+        Ok(())
+    }
+
+    /// Contest the owner reclaiming an expired lock. Only the lock's
+    /// beneficiary may call this, after expiry and within the configured
+    /// challenge window. Moves the lock to `Disputed` for the contract
+    /// owner to settle with `resolve_dispute`.
+    pub fn challenge_reclaim(env: Env, owner: Address, lock_id: u64) -> Result<(), VaultError> {
+        Self::require_init(&env)?;
+        let window = Self::challenge_window(env.clone()).ok_or(VaultError::InvalidConfig)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+        let beneficiary = entry.beneficiary.clone().ok_or(VaultError::NotRecipient)?;
+        beneficiary.require_auth();
+
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        let now = env.ledger().sequence() as u64;
+        if now <= entry.expires_at {
+            return Err(VaultError::LockNotExpired);
+        }
+        if now > entry.expires_at.saturating_add(window) {
+            return Err(VaultError::ChallengeClosed);
+        }
+
+        entry.status = LockStatus::Disputed;
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        let challenge_key = DataKey::Challenge(owner.clone(), lock_id);
+        env.storage().persistent().set(&challenge_key, &now);
+        env.storage().persistent().extend_ttl(&challenge_key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "challenge"), owner),
+            (lock_id, beneficiary),
+        );
+        Ok(())
+    }
+
+    /// Settle a disputed lock. `Some(to)` releases the funds to `to` (minus
+    /// the release fee); `None` returns them to the lock owner's balance.
+    /// Contract owner only.
+    pub fn resolve_dispute(
+        env: Env,
+        owner: Address,
+        lock_id: u64,
+        release_to: Option<Address>,
+    ) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status != LockStatus::Disputed {
+            return Err(VaultError::LockNotActive);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Challenge(owner.clone(), lock_id));
+
+        match &release_to {
+            Some(to) => {
+                Self::release_funds(&env, &owner, &entry, to)?;
+                entry.status = LockStatus::Released;
+            }
+            None => {
+                Self::add_locked(&env, &entry.token, -entry.amount);
+                Self::credit(&env, &owner, &entry.token, entry.amount);
+                entry.status = LockStatus::Expired;
+            }
+        }
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        Self::unindex_incoming(&env, &owner, lock_id, &entry);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (Symbol::new(&env, "resolve"), owner),
+            (lock_id, release_to, entry.amount),
+        );
+        Ok(())
+    }
+
+    // ─── Configuration ──────────────────────────────────────────────────
+
+    /// Set the event verbosity (`EVENTS_NONE`, `EVENTS_KEY` or
+    /// `EVENTS_VERBOSE`). Only the contract owner can call this.
+    pub fn set_event_level(env: Env, level: u32) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if level > EVENTS_VERBOSE {
+            return Err(VaultError::InvalidConfig);
+        }
+        env.storage().instance().set(&DataKey::EventLevel, &level);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "event_level"),), level);
+        Ok(())
+    }
+
+    /// Set the DEX adapter used by `lock_swapped`. Owner only.
+    pub fn set_swap_adapter(env: Env, adapter: Address) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        env.storage().instance().set(&DataKey::SwapAdapter, &adapter);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "swap_adapter"),), adapter);
+        Ok(())
+    }
+
+    /// Set the release fee in basis points (max 10_000). The fee is taken
+    /// from each released amount and credited to the contract owner's
+    /// vault balance. Owner only.
+    pub fn set_fee_bps(env: Env, bps: u32) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if bps > 10_000 {
+            return Err(VaultError::InvalidConfig);
+        }
+        env.storage().instance().set(&DataKey::FeeBps, &bps);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "fee_bps"),), bps);
+        Ok(())
+    }
+
+    /// Override the release fee for `token` (max 10_000 bps). Takes
+    /// precedence over `set_fee_bps`, including an override of 0. Owner only.
+    pub fn set_token_fee(env: Env, token: Address, bps: u32) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if bps > 10_000 {
+            return Err(VaultError::InvalidConfig);
+        }
+        let key = DataKey::TokenFeeBps(token.clone());
+        env.storage().persistent().set(&key, &bps);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "token_fee"), token), bps);
+        Ok(())
+    }
+
+    /// Cap the `lock_with_interest` rate for `token`, in basis points per
+    /// 1000 ledgers. Defaults to 0, i.e. no interest-bearing locks. Owner
+    /// only.
+    pub fn set_max_interest_rate(env: Env, token: Address, bps: u32) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        let key = DataKey::MaxInterestRate(token.clone());
+        env.storage().persistent().set(&key, &bps);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "max_interest_rate"), token), bps);
+        Ok(())
+    }
+
+    /// Drop the fee override for `token`, falling back to the global fee.
+    /// Owner only.
+    pub fn clear_token_fee(env: Env, token: Address) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        env.storage()
+            .persistent()
+            .remove(&DataKey::TokenFeeBps(token.clone()));
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "token_fee_clear"),), token);
+        Ok(())
+    }
+
+    /// Require withdrawals above `threshold` to wait `delay` ledgers via
+    /// `request_withdraw`/`execute_withdraw`. A `delay` of 0 turns the
+    /// policy off. Owner only.
+    pub fn set_withdraw_delay(env: Env, threshold: i128, delay: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if threshold < 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        if delay == 0 {
+            env.storage().instance().remove(&DataKey::WithdrawDelay);
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::WithdrawDelay, &WithdrawDelay { threshold, delay });
+        }
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "withdraw_delay"),),
+            (threshold, delay),
+        );
+        Ok(())
+    }
+
+    /// Limit every account to withdrawing at most `max_amount` of each token
+    /// per `window` ledgers. A `window` of 0 removes the limit. Owner only.
+    pub fn set_withdraw_limit(env: Env, max_amount: i128, window: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if max_amount <= 0 && window > 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        if window == 0 {
+            env.storage().instance().remove(&DataKey::WithdrawLimit);
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::WithdrawLimit, &WithdrawLimit { max_amount, window });
+        }
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "withdraw_limit"),),
+            (max_amount, window),
+        );
+        Ok(())
+    }
+
+    /// Set the fee `extend_lock_paid` charges, in units of the extended
+    /// lock's token. Passing 0 disables paid extensions. Owner only.
+    pub fn set_extension_fee(env: Env, fee: i128) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if fee < 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        if fee == 0 {
+            env.storage().instance().remove(&DataKey::ExtensionFee);
+        } else {
+            env.storage().instance().set(&DataKey::ExtensionFee, &fee);
+        }
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "extension_fee"),), fee);
+        Ok(())
+    }
+
+    /// When enabled, plain `deposit` (and `deposit_native`) is rejected
+    /// with `MemoRequired`; deposits must go through `deposit_with_memo`
+    /// with a non-empty memo. Owner only.
+    pub fn set_require_memo(env: Env, enabled: bool) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        env.storage().instance().set(&DataKey::RequireMemo, &enabled);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "require_memo"),), enabled);
+        Ok(())
+    }
+
+    /// Let beneficiaries `challenge_reclaim` for `ledgers` after a lock
+    /// expires; until the window closes the owner cannot reclaim locks that
+    /// have a beneficiary. Passing 0 disables challenges. Owner only.
+    pub fn set_challenge_window(env: Env, ledgers: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if ledgers == 0 {
+            env.storage().instance().remove(&DataKey::ChallengeWindow);
+        } else {
+            env.storage().instance().set(&DataKey::ChallengeWindow, &ledgers);
+        }
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "challenge_window"),), ledgers);
+        Ok(())
+    }
+
+    /// Open `emergency_withdraw` from ledger `ledger` onward. Passing 0
+    /// closes it again. Owner only.
+    pub fn set_emergency_at(env: Env, ledger: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if ledger == 0 {
+            env.storage().instance().remove(&DataKey::EmergencyAt);
+        } else {
+            env.storage().instance().set(&DataKey::EmergencyAt, &ledger);
+        }
+        Self::emit(&env, EVENTS_KEY, (Symbol::new(&env, "emergency_at"),), ledger);
+        Ok(())
+    }
+
+    /// Set how many ledgers ahead `lock_default` locks expire. Passing 0
+    /// removes the default. Owner only.
+    pub fn set_default_lock_duration(env: Env, ledgers: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if ledgers == 0 {
+            env.storage().instance().remove(&DataKey::DefaultLockDuration);
+        } else {
+            env.storage().instance().set(&DataKey::DefaultLockDuration, &ledgers);
+        }
+        Self::emit(
+            &env,
+            EVENTS_VERBOSE,
+            (Symbol::new(&env, "default_lock_duration"),),
+            ledgers,
+        );
+        Ok(())
+    }
+
+    /// When enabled, a `withdraw` that would leave less than the token's
+    /// `min_deposit` also sends that remainder and deletes the balance
+    /// entry. Owner only.
+    pub fn set_auto_dust(env: Env, enabled: bool) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        env.storage().instance().set(&DataKey::AutoDust, &enabled);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "auto_dust"),), enabled);
+        Ok(())
+    }
+
+    /// Cap how many ledgers ahead a new lock may expire. Passing 0 removes
+    /// the cap. Owner only.
+    pub fn set_max_lock_duration(env: Env, ledgers: u64) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if ledgers == 0 {
+            env.storage().instance().remove(&DataKey::MaxLockDuration);
+        } else {
+            env.storage().instance().set(&DataKey::MaxLockDuration, &ledgers);
+        }
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "max_lock_duration"),), ledgers);
+        Ok(())
+    }
+
+    /// Reject deposits of `token` below `amount`. Passing 0 removes the
+    /// minimum. Owner only.
+    pub fn set_min_deposit(env: Env, token: Address, amount: i128) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if amount < 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        let key = DataKey::MinDeposit(token.clone());
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &amount);
+            env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        }
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "min_deposit"), token), amount);
+        Ok(())
+    }
+
+    /// Cap each owner's available balance of `token` at `cap`; deposits that
+    /// would exceed it are rejected. Passing 0 removes the cap. Owner only.
+    pub fn set_deposit_cap(env: Env, token: Address, cap: i128) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        if cap < 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        let key = DataKey::DepositCap(token.clone());
+        if cap == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &cap);
+            env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        }
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "deposit_cap"), token), cap);
+        Ok(())
+    }
+
+    /// When enabled, `get_lock` and the list views report an active lock
+    /// past its expiry as `Expired` without writing state. Stored status
+    /// still only changes on `release`/`reclaim`. Owner only.
+    pub fn set_expire_on_read(env: Env, enabled: bool) -> Result<(), VaultError> {
+        Self::require_owner(&env)?;
+        env.storage().instance().set(&DataKey::ExpireOnRead, &enabled);
+        Self::emit(&env, EVENTS_VERBOSE, (Symbol::new(&env, "expire_on_read"),), enabled);
+        Ok(())
+    }
+
+    // ─── Read-only queries ──────────────────────────────────────────────
+
+    /// Get the deposited (unlocked) balance for an owner+token pair.
+    pub fn balance(env: Env, owner: Address, token: Address) -> i128 {
+        let key = DataKey::Balance(owner, token);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+
+    /// Remaining amount `spender` may withdraw from `owner`'s balance.
+    pub fn allowance(env: Env, owner: Address, spender: Address, token: Address) -> i128 {
+        let key = DataKey::Allowance(owner, spender, token);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+
+    /// Active locks naming `beneficiary`, as `(owner, lock_id, amount)`.
+    pub fn incoming_locks(env: Env, beneficiary: Address) -> Vec<(Address, u64, i128)> {
+        let index: Vec<(Address, u64)> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::IncomingLocks(beneficiary))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut out = Vec::new(&env);
+        for (owner, lock_id) in index.iter() {
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            if let Some(entry) = entry {
+                if Self::viewed(&env, entry.clone()).status == LockStatus::Active {
+                    out.push_back((owner, lock_id, entry.amount));
+                }
+            }
+        }
+        out
+    }
+
+    /// Available balance minus any pending delayed withdrawal of the same
+    /// token, floored at 0. What the owner can still lock or withdraw
+    /// without disturbing the pending request.
+    pub fn spendable(env: Env, owner: Address, token: Address) -> i128 {
+        let pending = Self::pending_withdraw(env.clone(), owner.clone(), token.clone())
+            .map(|p| p.amount)
+            .unwrap_or(0);
+        (Self::balance(env, owner, token) - pending).max(0)
+    }
+
+    /// Get a specific lock entry.
+    pub fn get_lock(env: Env, owner: Address, lock_id: u64) -> Result<LockEntry, VaultError> {
+        let key = DataKey::Lock(owner, lock_id);
+        let entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(VaultError::LockNotFound)?;
+        Ok(Self::viewed(&env, entry))
+    }
+
+    /// The stored lock entry's canonical XDR encoding, for off-chain hashing
+    /// and archival. Unlike `get_lock`, `ExpireOnRead` is not applied.
+    pub fn lock_xdr(env: Env, owner: Address, lock_id: u64) -> Result<Bytes, VaultError> {
+        let entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lock(owner, lock_id))
+            .ok_or(VaultError::LockNotFound)?;
+        Ok(entry.to_xdr(&env))
+    }
+
+    /// Token, amount, expiry, status and beneficiary of a lock in one read.
+    /// `status` follows the same `ExpireOnRead` policy as `get_lock`.
+    pub fn lock_summary(env: Env, owner: Address, lock_id: u64) -> Result<LockSummary, VaultError> {
+        let entry = Self::get_lock(env, owner, lock_id)?;
+        Ok(LockSummary {
+            token: entry.token,
+            amount: entry.amount,
+            expires_at: entry.expires_at,
+            status: entry.status,
+            beneficiary: entry.beneficiary,
+        })
+    }
+
+    /// Read a basket by id.
+    pub fn get_basket(env: Env, owner: Address, basket_id: u64) -> Result<BasketLock, VaultError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Basket(owner, basket_id))
+            .ok_or(VaultError::LockNotFound)
+    }
+
+    /// True if `release` would currently accept this lock: it is `Active`
+    /// and the current ledger has not passed `expires_at`.
+    pub fn is_releasable(env: Env, owner: Address, lock_id: u64) -> bool {
+        let entry: Option<LockEntry> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lock(owner, lock_id));
+        match entry {
+            Some(entry) => {
+                entry.status == LockStatus::Active
+                    && env.ledger().sequence() as u64 <= entry.expires_at
+            }
+            None => false,
+        }
+    }
+
+    /// The address allowed to `reclaim_as` for `owner`, if any.
+    pub fn recovery(env: Env, owner: Address) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Recovery(owner))
+    }
+
+    /// Whether `owner` has frozen their own account via `freeze_self`.
+    pub fn is_self_frozen(env: Env, owner: Address) -> bool {
+        env.storage().persistent().has(&DataKey::SelfFrozen(owner))
+    }
+
+    /// Whether `withdraw` sweeps sub-minimum remainders.
+    pub fn auto_dust(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::AutoDust)
+            .unwrap_or(false)
+    }
+
+    /// Whether deposits must carry a memo.
+    pub fn require_memo(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::RequireMemo)
+            .unwrap_or(false)
+    }
+
+    /// Whether reads report past-expiry active locks as `Expired`.
+    pub fn expire_on_read(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::ExpireOnRead)
+            .unwrap_or(false)
+    }
+
+    /// Get the current event verbosity. Defaults to `EVENTS_VERBOSE`.
+    pub fn event_level(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::EventLevel)
+            .unwrap_or(EVENTS_VERBOSE)
+    }
+
+    /// Get the delayed-withdrawal policy, if one is set.
+    pub fn withdraw_delay(env: Env) -> Option<WithdrawDelay> {
+        env.storage().instance().get(&DataKey::WithdrawDelay)
+    }
+
+    /// Get the pending delayed withdrawal for an owner+token pair.
+    pub fn pending_withdraw(env: Env, owner: Address, token: Address) -> Option<PendingWithdraw> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PendingWithdraw(owner, token))
+    }
+
+    /// Get the paid-extension fee, if extensions are enabled.
+    pub fn extension_fee(env: Env) -> Option<i128> {
+        env.storage().instance().get(&DataKey::ExtensionFee)
+    }
+
+    /// Number of distinct addresses that have ever deposited.
+    pub fn unique_owners(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UniqueOwners)
+            .unwrap_or(0)
+    }
+
+    /// Highest `lock_with_interest` rate accepted for `token`. Defaults to 0.
+    pub fn max_interest_rate(env: Env, token: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MaxInterestRate(token))
+            .unwrap_or(0)
+    }
+
+    /// Balance of the interest reserve for `token`.
+    pub fn reserve(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Reserve(token))
+            .unwrap_or(0)
+    }
+
+    /// How much of a `lock_vesting` lock `release_vested` would pay now:
+    /// `total * (now - start) / (end - start)`, clamped to the schedule,
+    /// less what was already released. 0 once the lock is closed, and for
+    /// unknown or non-vesting locks.
+    pub fn unlocked_amount(env: Env, owner: Address, lock_id: u64) -> i128 {
+        let entry: Option<LockEntry> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lock(owner.clone(), lock_id));
+        let schedule: Option<VestingSchedule> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Vesting(owner, lock_id));
+        match (entry, schedule) {
+            (Some(entry), Some(schedule)) => Self::vested_amount(&env, &entry, &schedule),
+            _ => 0,
+        }
+    }
+
+    /// Interest a lock has accrued so far, as `release` would pay it now.
+    pub fn accrued_interest(env: Env, owner: Address, lock_id: u64) -> Result<i128, VaultError> {
+        let entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lock(owner, lock_id))
+            .ok_or(VaultError::LockNotFound)?;
+        Ok(Self::interest_on(&env, &entry))
+    }
+
+    /// Get the reclaim challenge window in ledgers, if challenges are enabled.
+    pub fn challenge_window(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::ChallengeWindow)
+    }
+
+    /// Get the ledger from which `emergency_withdraw` is open, if set.
+    pub fn emergency_at(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::EmergencyAt)
+    }
+
+    /// Ledger at which an open challenge on a lock was filed.
+    pub fn challenge(env: Env, owner: Address, lock_id: u64) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::Challenge(owner, lock_id))
+    }
+
+    /// Get the `lock_default` duration in ledgers, if one is set.
+    pub fn default_lock_duration(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::DefaultLockDuration)
+    }
+
+    /// Get the lock duration cap in ledgers, if one is set.
+    pub fn max_lock_duration(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::MaxLockDuration)
+    }
+
+    /// Smallest accepted deposit of `token`. 0 means no minimum.
+    pub fn min_deposit(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MinDeposit(token))
+            .unwrap_or(0)
+    }
+
+    /// Per-owner cap on the available balance of `token`, if set.
+    pub fn deposit_cap(env: Env, token: Address) -> Option<i128> {
+        env.storage().persistent().get(&DataKey::DepositCap(token))
+    }
+
+    /// Get the withdrawal rate limit, if any.
+    pub fn withdraw_limit(env: Env) -> Option<WithdrawLimit> {
+        env.storage().instance().get(&DataKey::WithdrawLimit)
+    }
+
+    /// Get the release fee in basis points. Defaults to 0.
+    pub fn fee_bps(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::FeeBps).unwrap_or(0)
+    }
+
+    /// Release fee in basis points for `token`: its override if set,
+    /// otherwise the global fee.
+    pub fn token_fee_bps(env: Env, token: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TokenFeeBps(token))
+            .unwrap_or_else(|| Self::fee_bps(env))
+    }
+
+    /// Quote the fee `release` would take from a lock of `amount` of
+    /// `token`, at `token_fee_bps(token)`. Pure read.
+    pub fn quote_release_fee(env: Env, token: Address, amount: i128) -> i128 {
+        Self::release_fee(&env, &token, amount)
+    }
+
+    /// Solvency check for `token`: returns `(held, accounted)` where `held`
+    /// is the vault's actual token balance and `accounted` is the sum of all
+    /// available balances plus active lock amounts. `held > accounted` means
+    /// tokens were sent directly to the contract; `held < accounted` signals
+    /// an accounting bug.
+    pub fn solvency(env: Env, token: Address) -> (i128, i128) {
+        let held = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        (held, Self::accounted_total(&env, &token))
+    }
+
+    /// Accumulators and actual balance for `token`. Fees credited to the
+    /// vault owner are also counted in `total_deposited` until withdrawn.
+    pub fn token_stats(env: Env, token: Address) -> TokenStats {
+        let get = |key: DataKey| -> i128 { env.storage().persistent().get(&key).unwrap_or(0) };
+        TokenStats {
+            total_deposited: get(DataKey::TotalBalance(token.clone())),
+            total_locked: get(DataKey::TotalLocked(token.clone())),
+            accrued_fees: get(DataKey::AccruedFees(token.clone())),
+            actual_balance: token::Client::new(&env, &token)
+                .balance(&env.current_contract_address()),
+        }
+    }
+
+    /// Self-diagnostic for CI and health checks. Returns false if:
+    /// - the vault holds less of `token` than its accumulators account for,
+    /// - `owner`'s available balance exceeds the token's `TotalBalance`,
+    /// - `owner`'s open locks (the last `MAX_SCAN` ids) exceed `TotalLocked`,
+    /// - any of those locks is still `Active` more than `EXPIRY_GRACE`
+    ///   ledgers after `expires_at`.
+    pub fn check_invariants(env: Env, owner: Address, token: Address) -> bool {
+        let (held, accounted) = Self::solvency(env.clone(), token.clone());
+        if held < accounted {
+            return false;
+        }
+
+        let total_available: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalBalance(token.clone()))
+            .unwrap_or(0);
+        let total_locked: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalLocked(token.clone()))
+            .unwrap_or(0);
+        if Self::balance(env.clone(), owner.clone(), token.clone()) > total_available {
+            return false;
+        }
+
+        let next = Self::lock_count(env.clone(), owner.clone());
+        let now = env.ledger().sequence() as u64;
+        let mut locked: i128 = 0;
+        for lock_id in next.saturating_sub(MAX_SCAN as u64)..next {
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            let Some(entry) = entry else { continue };
+            if entry.token != token {
+                continue;
+            }
+            match entry.status {
+                LockStatus::Active => {
+                    if now > entry.expires_at.saturating_add(EXPIRY_GRACE) {
+                        return false;
+                    }
+                    locked += entry.amount;
+                }
+                LockStatus::Disputed | LockStatus::Offered => locked += entry.amount,
+                LockStatus::Released | LockStatus::Expired | LockStatus::Pending => {}
+            }
+        }
+        locked <= total_locked
+    }
+
+    /// Address of the native XLM Stellar Asset Contract on this network.
+    pub fn native_token(env: Env) -> Address {
+        // XDR of `Asset::Native` (ASSET_TYPE_NATIVE = 0)
+        let native = Bytes::from_array(&env, &[0u8; 4]);
+        env.deployer().with_stellar_asset(native).deployed_address()
+    }
+
+    /// Largest page any `list_*` call returns. Larger limits are clamped.
+    pub fn max_page_size() -> u32 {
+        MAX_PAGE_SIZE
+    }
+
+    /// Fetch `owner`'s locks for each id in `lock_ids`, in input order, with
+    /// `None` for ids that don't exist. Only the first `MAX_PAGE_SIZE` ids
+    /// are read.
+    pub fn get_locks(env: Env, owner: Address, lock_ids: Vec<u64>) -> Vec<Option<LockEntry>> {
+        let mut out = Vec::new(&env);
+        for lock_id in lock_ids.iter().take(MAX_PAGE_SIZE as usize) {
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            out.push_back(entry.map(|entry| Self::viewed(&env, entry)));
+        }
+        out
+    }
+
+    /// Page through `owner`'s locks by lock_id, starting at `start`.
+    pub fn list_locks(env: Env, owner: Address, start: u64, limit: u32) -> Vec<(u64, LockEntry)> {
+        let next: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NextLockId(owner.clone()))
+            .unwrap_or(0);
+        let end = next.min(start.saturating_add(limit.min(MAX_PAGE_SIZE) as u64));
+
+        let mut page = Vec::new(&env);
+        for lock_id in start..end {
+            if let Some(entry) = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id))
+            {
+                page.push_back((lock_id, Self::viewed(&env, entry)));
+            }
+        }
+        page
+    }
+
+    /// Page through `owner`'s closed (`Released` or `Expired`) locks for
+    /// history views. Scans at most `MAX_SCAN` ids from `start` and returns
+    /// up to `limit` (capped at `MAX_PAGE_SIZE`) entries; resume from the
+    /// last returned id + 1.
+    pub fn closed_locks(env: Env, owner: Address, start: u64, limit: u32) -> Vec<(u64, LockEntry)> {
+        let next = Self::lock_count(env.clone(), owner.clone());
+        let end = next.min(start.saturating_add(MAX_SCAN as u64));
+        let limit = limit.min(MAX_PAGE_SIZE);
+
+        let mut page = Vec::new(&env);
+        for lock_id in start..end {
+            if page.len() >= limit {
+                break;
+            }
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            if let Some(entry) = entry {
+                if matches!(entry.status, LockStatus::Released | LockStatus::Expired) {
+                    page.push_back((lock_id, entry));
+                }
+            }
+        }
+        page
+    }
+
+    /// Number of lock ids ever assigned to `owner` (ids run from 0), i.e.
+    /// the exclusive upper bound for `list_locks` paging. Includes closed
+    /// and transferred-away locks.
+    pub fn lock_count(env: Env, owner: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::NextLockId(owner))
+            .unwrap_or(0)
+    }
+
+    /// Whether `owner` holds nothing in the vault: no available balance in
+    /// any token they've been credited, and no funded lock (`Active`,
+    /// `Offered` or `Disputed`) or active basket among the last `MAX_SCAN`
+    /// lock and basket ids.
+    pub fn is_empty(env: Env, owner: Address) -> bool {
+        let tokens: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerTokens(owner.clone()))
+            .unwrap_or(Vec::new(&env));
+        for token in tokens.iter().take(MAX_SCAN as usize) {
+            if Self::balance(env.clone(), owner.clone(), token) > 0 {
+                return false;
+            }
+        }
+
+        let next = Self::lock_count(env.clone(), owner.clone());
+        for lock_id in next.saturating_sub(MAX_SCAN as u64)..next {
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            if entry.is_some_and(|e| {
+                matches!(
+                    e.status,
+                    LockStatus::Active | LockStatus::Offered | LockStatus::Disputed
+                )
+            }) {
+                return false;
+            }
+        }
+
+        let next: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NextBasketId(owner.clone()))
+            .unwrap_or(0);
+        for basket_id in next.saturating_sub(MAX_SCAN as u64)..next {
+            let basket: Option<BasketLock> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Basket(owner.clone(), basket_id));
+            if basket.is_some_and(|b| b.status == LockStatus::Active) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Value of `owner`'s available balances in `oracle`'s reference unit:
+    /// the sum of `balance * price / PRICE_SCALE` over every token they've
+    /// been credited (up to `MAX_SCAN`). Tokens the oracle doesn't price,
+    /// whose lookup fails, or whose value would overflow the total are
+    /// skipped. Locked amounts are not included.
+    pub fn portfolio_value(env: Env, owner: Address, oracle: Address) -> i128 {
+        let tokens: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerTokens(owner.clone()))
+            .unwrap_or(Vec::new(&env));
+        let client = PriceOracleClient::new(&env, &oracle);
+
+        let mut total: i128 = 0;
+        for token in tokens.iter().take(MAX_SCAN as usize) {
+            let balance = Self::balance(env.clone(), owner.clone(), token.clone());
+            if balance == 0 {
+                continue;
+            }
+            if let Ok(Ok(Some(price))) = client.try_price(&token) {
+                let value = balance
+                    .checked_mul(price)
+                    .and_then(|v| total.checked_add(v / PRICE_SCALE));
+                if let Some(sum) = value {
+                    total = sum;
+                }
+            }
+        }
+        total
+    }
+
+    /// Earliest `expires_at` among the owner's active locks, or `None` if
+    /// there are none. Only the most recent `MAX_SCAN` lock ids are checked.
+    pub fn next_expiry(env: Env, owner: Address) -> Option<u64> {
+        let next: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NextLockId(owner.clone()))
+            .unwrap_or(0);
+        let start = next.saturating_sub(MAX_SCAN as u64);
+
+        let mut earliest: Option<u64> = None;
+        for lock_id in start..next {
+            let entry: Option<LockEntry> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Lock(owner.clone(), lock_id));
+            if let Some(entry) = entry {
+                if entry.status == LockStatus::Active
+                    && earliest.is_none_or(|e| entry.expires_at < e)
+                {
+                    earliest = Some(entry.expires_at);
+                }
+            }
+        }
+        earliest
+    }
+
+    /// Get the contract owner.
+    pub fn owner(env: Env) -> Result<Address, VaultError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Owner)
+            .ok_or(VaultError::NotInitialized)
+    }
+
+    // ─── Internal ───────────────────────────────────────────────────────
+
+    fn require_init(env: &Env) -> Result<(), VaultError> {
+        if !env.storage().instance().has(&DataKey::Owner) {
+            return Err(VaultError::NotInitialized);
+        }
+        env.storage().instance().extend_ttl(518_400, 518_400);
+        Ok(())
+    }
+
+    fn require_owner(env: &Env) -> Result<(), VaultError> {
+        let owner: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Owner)
+            .ok_or(VaultError::NotInitialized)?;
+        owner.require_auth();
+        env.storage().instance().extend_ttl(518_400, 518_400);
+        Ok(())
+    }
+
+    /// Validate a new lock's `expires_at` against the current ledger and the
+    /// configured duration cap.
+    fn check_expiry(env: &Env, expires_at: u64) -> Result<(), VaultError> {
+        let current_ledger = env.ledger().sequence() as u64;
+        if expires_at <= current_ledger {
+            return Err(VaultError::InvalidExpiry);
+        }
+        if let Some(max) = Self::max_lock_duration(env.clone()) {
+            if expires_at - current_ledger > max {
+                return Err(VaultError::LockTooLong);
+            }
+        }
+        Ok(())
+    }
+
+    /// Publish an event if the configured verbosity is at least `level`.
+    fn emit<T: Topics, D: IntoVal<Env, Val>>(env: &Env, level: u32, topics: T, data: D) {
+        if Self::event_level(env.clone()) >= level {
+            env.events().publish(topics, data);
+        }
+    }
+
+    /// Move an expired lock's funds back to the owner's balance. Returns
+    /// the amount reclaimed.
+    fn reclaim_lock(env: &Env, owner: &Address, lock_id: u64) -> Result<i128, VaultError> {
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        let mut entry: LockEntry = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .ok_or(VaultError::LockNotFound)?;
+
+        if entry.status == LockStatus::Disputed {
+            return Err(VaultError::LockDisputed);
+        }
+        if entry.status != LockStatus::Active {
+            return Err(VaultError::LockNotActive);
+        }
+        let current_ledger = env.ledger().sequence() as u64;
+        if current_ledger <= entry.expires_at {
+            return Err(VaultError::LockNotExpired);
+        }
+        // Past `end_ledger` a vesting lock is fully vested: it belongs to
+        // the beneficiary, who claims it with `release_vested`
+        Self::require_not_vesting(env, owner, lock_id)?;
+        if entry.beneficiary.is_some() {
+            if let Some(window) = Self::challenge_window(env.clone()) {
+                if current_ledger <= entry.expires_at.saturating_add(window) {
+                    return Err(VaultError::ChallengePeriod);
+                }
+            }
+        }
+
+        // Return to owner's balance; interest is only paid to a recipient
+        Self::add_locked(env, &entry.token, -entry.amount);
+        Self::credit(env, owner, &entry.token, entry.amount);
+
+        entry.status = LockStatus::Expired;
+        env.storage().persistent().set(&lock_key, &entry);
+        Self::unindex_incoming(env, owner, lock_id, &entry);
+
+        Self::emit(
+            env,
+            EVENTS_KEY,
+            (Symbol::new(env, "reclaim"), owner.clone()),
+            (lock_id, entry.amount),
+        );
+        Ok(entry.amount)
+    }
+
+    /// Check deposit limits, transfer `amount` of `token` from `owner` into
+    /// the vault and credit their balance.
+    fn pull_deposit(
+        env: &Env,
+        owner: &Address,
+        token: &Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if amount < Self::min_deposit(env.clone(), token.clone()) {
+            return Err(VaultError::BelowMinDeposit);
+        }
+        if let Some(cap) = Self::deposit_cap(env.clone(), token.clone()) {
+            let current = Self::balance(env.clone(), owner.clone(), token.clone());
+            if current + amount > cap {
+                return Err(VaultError::CapExceeded);
+            }
+        }
+
+        // Transfer tokens from owner → this contract
+        let client = token::Client::new(env, token);
+        client.transfer(owner, &env.current_contract_address(), &amount);
+
+        // Credit internal balance
+        Self::credit(env, owner, token, amount);
+
+        let seen_key = DataKey::OwnerSeen(owner.clone());
+        if !env.storage().persistent().has(&seen_key) {
+            env.storage().persistent().set(&seen_key, &true);
+            let count = Self::unique_owners(env.clone());
+            env.storage().persistent().set(&DataKey::UniqueOwners, &(count + 1));
+            env.storage()
+                .persistent()
+                .extend_ttl(&DataKey::UniqueOwners, 518_400, 518_400);
+        }
+        env.storage().persistent().extend_ttl(&seen_key, 518_400, 518_400);
+        Ok(())
+    }
+
+    /// Debit `amount` from the owner's balance, send it to them and emit
+    /// the withdraw event.
+    fn send_from_balance(
+        env: &Env,
+        owner: &Address,
+        token: &Address,
+        amount: i128,
+        to: &Address,
+    ) -> Result<(), VaultError> {
+        Self::require_not_frozen(env, owner)?;
+        Self::check_rate_limit(env, owner, token, amount)?;
+        Self::debit(env, owner, token, amount)?;
+
+        // Transfer tokens from contract → recipient
+        let client = token::Client::new(env, token);
+        client.transfer(&env.current_contract_address(), to, &amount);
+
+        Self::emit(
+            env,
+            EVENTS_KEY,
+            (Symbol::new(env, "withdraw"), owner.clone(), token.clone()),
+            amount,
+        );
+        Ok(())
+    }
+
+    /// The part of a vesting lock's schedule that has vested and not been
+    /// released yet; see `unlocked_amount`.
+    fn vested_amount(env: &Env, entry: &LockEntry, schedule: &VestingSchedule) -> i128 {
+        if entry.status != LockStatus::Active {
+            return 0;
+        }
+        let (start, end) = (schedule.start_ledger, schedule.end_ledger);
+        let now = (env.ledger().sequence() as u64).clamp(start, end);
+        let elapsed = (now - start) as i128;
+        let duration = (end - start) as i128;
+        let vested = schedule.total * elapsed / duration - schedule.released;
+        vested.clamp(0, entry.amount)
+    }
+
+    /// Reject operations that would move a `lock_vesting` lock's funds or
+    /// re-key it away from its `VestingSchedule`.
+    fn require_not_vesting(env: &Env, owner: &Address, lock_id: u64) -> Result<(), VaultError> {
+        if Self::is_vesting(env, owner, lock_id) {
+            return Err(VaultError::VestingLock);
+        }
+        Ok(())
+    }
+
+    fn is_vesting(env: &Env, owner: &Address, lock_id: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Vesting(owner.clone(), lock_id))
+    }
+
+    fn require_not_frozen(env: &Env, owner: &Address) -> Result<(), VaultError> {
+        if Self::is_self_frozen(env.clone(), owner.clone()) {
+            return Err(VaultError::AccountFrozen);
+        }
+        Ok(())
+    }
+
+    /// Transfer `amount` of `token` from the vault to `recipient`, keeping
+    /// the release fee and crediting it to the contract owner's balance.
+    /// Returns the fee taken, or `TransferFailed` if the token rejects the
+    /// transfer; the caller's error return rolls back any state it changed.
+    fn pay_out(
+        env: &Env,
+        token: &Address,
+        recipient: &Address,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        let fee = Self::release_fee(env, token, amount);
+
+        Self::send(env, token, recipient, amount - fee)?;
+
+        if fee > 0 {
+            let vault_owner: Address = env.storage().instance().get(&DataKey::Owner).unwrap();
+            Self::credit(env, &vault_owner, token, fee);
+            Self::add_total(env, DataKey::AccruedFees(token.clone()), fee);
+        }
+        Ok(fee)
+    }
+
+    /// Fee on releasing `amount` of `token`, at that token's effective rate.
+    fn release_fee(env: &Env, token: &Address, amount: i128) -> i128 {
+        if amount <= 0 {
+            return 0;
+        }
+        amount * Self::token_fee_bps(env.clone(), token.clone()) as i128 / 10_000
+    }
+
+    /// Pay `owner`'s closing lock out to `recipient`: its amount minus the
+    /// release fee, plus any accrued interest from the reserve, fee-free.
+    /// No interest is paid when the recipient is the owner.
+    fn release_funds(
+        env: &Env,
+        owner: &Address,
+        entry: &LockEntry,
+        recipient: &Address,
+    ) -> Result<(), VaultError> {
+        if recipient != owner {
+            let interest = Self::take_interest(env, entry)?;
+            if interest > 0 {
+                Self::send(env, &entry.token, recipient, interest)?;
+            }
+        }
+        Self::add_locked(env, &entry.token, -entry.amount);
+        Self::pay_out(env, &entry.token, recipient, entry.amount)?;
+        Ok(())
+    }
+
+    /// Take `entry`'s accrued interest out of the token's reserve, failing
+    /// with `ReserveInsufficient` if the reserve can't cover it.
+    fn take_interest(env: &Env, entry: &LockEntry) -> Result<i128, VaultError> {
+        let interest = Self::interest_on(env, entry);
+        if interest > 0 {
+            if Self::reserve(env.clone(), entry.token.clone()) < interest {
+                return Err(VaultError::ReserveInsufficient);
+            }
+            Self::add_total(env, DataKey::Reserve(entry.token.clone()), -interest);
+        }
+        Ok(interest)
+    }
+
+    /// Transfer `amount` of `token` from the vault to `to`, mapping a
+    /// rejected transfer to `TransferFailed`.
+    fn send(env: &Env, token: &Address, to: &Address, amount: i128) -> Result<(), VaultError> {
+        let client = token::Client::new(env, token);
+        let sent = client.try_transfer(&env.current_contract_address(), to, &amount);
+        if !matches!(sent, Ok(Ok(()))) {
+            return Err(VaultError::TransferFailed);
+        }
+        Ok(())
+    }
+
+    /// Interest accrued on `entry` so far:
+    /// amount * rate * elapsed / (1000 * 10_000), elapsed capped at expiry.
+    fn interest_on(env: &Env, entry: &LockEntry) -> i128 {
+        if entry.rate_bps_per_1000_ledgers == 0 {
+            return 0;
+        }
+        let now = (env.ledger().sequence() as u64).min(entry.expires_at);
+        let elapsed = now.saturating_sub(entry.created_at) as i128;
+        entry.amount * entry.rate_bps_per_1000_ledgers as i128 * elapsed / 10_000_000
+    }
+
+    /// Count `amount` against the owner's current withdrawal window,
+    /// starting a new window once the previous one has elapsed.
+    fn check_rate_limit(
+        env: &Env,
+        owner: &Address,
+        token: &Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        let limit = match Self::withdraw_limit(env.clone()) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let now = env.ledger().sequence() as u64;
+        let key = DataKey::WithdrawWindow(owner.clone(), token.clone());
+        let mut window: WithdrawWindow = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(WithdrawWindow { start: now, amount: 0 });
+        if now >= window.start + limit.window {
+            window = WithdrawWindow { start: now, amount: 0 };
+        }
+
+        if window.amount + amount > limit.max_amount {
+            return Err(VaultError::RateLimited);
+        }
+        window.amount += amount;
+        env.storage().persistent().set(&key, &window);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Ok(())
+    }
+
+    /// Add `amount` to an owner's available balance and the token total.
+    fn credit(env: &Env, owner: &Address, token: &Address, amount: i128) {
+        let key = DataKey::Balance(owner.clone(), token.clone());
+        let prev: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(prev + amount));
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Self::add_total(env, DataKey::TotalBalance(token.clone()), amount);
+
+        let tokens_key = DataKey::OwnerTokens(owner.clone());
+        let mut tokens: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&tokens_key)
+            .unwrap_or(Vec::new(env));
+        if !tokens.contains(token) {
+            tokens.push_back(token.clone());
+            env.storage().persistent().set(&tokens_key, &tokens);
+        }
+        env.storage().persistent().extend_ttl(&tokens_key, 518_400, 518_400);
+    }
+
+    /// Remove `amount` from an owner's available balance and the token total.
+    fn debit(env: &Env, owner: &Address, token: &Address, amount: i128) -> Result<(), VaultError> {
+        let key = DataKey::Balance(owner.clone(), token.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if balance < amount {
+            return Err(VaultError::InsufficientFunds);
+        }
+        env.storage().persistent().set(&key, &(balance - amount));
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        Self::add_total(env, DataKey::TotalBalance(token.clone()), -amount);
+        Ok(())
+    }
+
+    fn accounted_total(env: &Env, token: &Address) -> i128 {
+        let available: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalBalance(token.clone()))
+            .unwrap_or(0);
+        let locked: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalLocked(token.clone()))
+            .unwrap_or(0);
+        available + locked + Self::reserve(env.clone(), token.clone())
+    }
+
+    /// Adjust the total amount held in active locks for `token`.
+    fn add_locked(env: &Env, token: &Address, delta: i128) {
+        Self::add_total(env, DataKey::TotalLocked(token.clone()), delta);
+    }
+
+    fn add_total(env: &Env, key: DataKey, delta: i128) {
+        let prev: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(prev + delta));
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+    }
+
+    fn active_entry(env: &Env, token: Address, amount: i128, expires_at: u64) -> LockEntry {
+        LockEntry {
+            token,
+            amount,
+            expires_at,
+            status: LockStatus::Active,
+            auto_renew: false,
+            renew_ledgers: 0,
+            metadata: Bytes::new(env),
+            beneficiary: None,
+            created_at: env.ledger().sequence() as u64,
+            rate_bps_per_1000_ledgers: 0,
+        }
+    }
+
+    /// Store a new lock under the owner's next lock_id and emit the lock
+    /// event. Locks with a beneficiary append it to the event data.
+    fn create_lock(env: &Env, owner: &Address, entry: LockEntry) -> u64 {
+        let lock_id = Self::next_lock_id(env, owner);
+        let lock_key = DataKey::Lock(owner.clone(), lock_id);
+        env.storage().persistent().set(&lock_key, &entry);
+        env.storage().persistent().extend_ttl(&lock_key, 518_400, 518_400);
+        Self::add_locked(env, &entry.token, entry.amount);
+        Self::index_incoming(env, owner, lock_id, &entry);
+
+        let topics = (Symbol::new(env, "lock"), owner.clone(), entry.token);
+        match entry.beneficiary {
+            Some(beneficiary) => Self::emit(
+                env,
+                EVENTS_KEY,
+                topics,
+                (lock_id, entry.amount, entry.expires_at, beneficiary),
+            ),
+            None => Self::emit(
+                env,
+                EVENTS_KEY,
+                topics,
+                (lock_id, entry.amount, entry.expires_at),
+            ),
+        }
+        lock_id
+    }
+
+    /// Apply the `ExpireOnRead` policy to a stored entry for display.
+    fn viewed(env: &Env, mut entry: LockEntry) -> LockEntry {
+        if entry.status == LockStatus::Active
+            && (env.ledger().sequence() as u64) > entry.expires_at
+            && Self::expire_on_read(env.clone())
+        {
+            entry.status = LockStatus::Expired;
+        }
+        entry
+    }
+
+    /// Record a lock under its beneficiary's `IncomingLocks`, if it has one.
+    fn index_incoming(env: &Env, owner: &Address, lock_id: u64, entry: &LockEntry) {
+        if let Some(beneficiary) = &entry.beneficiary {
+            let key = DataKey::IncomingLocks(beneficiary.clone());
+            let mut index: Vec<(Address, u64)> = env
+                .storage()
+                .persistent()
+                .get(&key)
+                .unwrap_or_else(|| Vec::new(env));
+            index.push_back((owner.clone(), lock_id));
+            env.storage().persistent().set(&key, &index);
+            env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+        }
+    }
+
+    /// Drop a closed lock from its beneficiary's `IncomingLocks`.
+    fn unindex_incoming(env: &Env, owner: &Address, lock_id: u64, entry: &LockEntry) {
+        if let Some(beneficiary) = &entry.beneficiary {
+            let key = DataKey::IncomingLocks(beneficiary.clone());
+            let mut index: Vec<(Address, u64)> = env
+                .storage()
+                .persistent()
+                .get(&key)
+                .unwrap_or_else(|| Vec::new(env));
+            if let Some(pos) = index.first_index_of((owner.clone(), lock_id)) {
+                index.remove(pos);
+                if index.is_empty() {
+                    env.storage().persistent().remove(&key);
+                } else {
+                    env.storage().persistent().set(&key, &index);
+                }
+            }
+        }
+    }
+
+    fn next_lock_id(env: &Env, owner: &Address) -> u64 {
+        let id_key = DataKey::NextLockId(owner.clone());
+        let lock_id: u64 = env.storage().persistent().get(&id_key).unwrap_or(0);
+        env.storage().persistent().set(&id_key, &(lock_id + 1));
+        lock_id
+    }
+}
+
+// ─── Tests ──────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::testutils::storage::Persistent as _;
+    use soroban_sdk::testutils::{Address as _, Events, Ledger};
     use soroban_sdk::token::{StellarAssetClient, TokenClient};
+    use soroban_sdk::Bytes;
+
+    fn setup_token<'a>(
+        env: &'a Env,
+        admin: &Address,
+    ) -> (Address, TokenClient<'a>, StellarAssetClient<'a>) {
+        let addr = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let client = TokenClient::new(env, &addr);
+        let admin_client = StellarAssetClient::new(env, &addr);
+        (addr, client, admin_client)
+    }
+
+    // Storage layout of the Stellar Asset Contract's balance entries.
+    #[contracttype]
+    enum SacKey {
+        Balance(Address),
+    }
+
+    #[contracttype]
+    struct SacBalance {
+        amount: i128,
+        authorized: bool,
+        clawback: bool,
+    }
+
+    /// Deploy the native XLM SAC and give `to` a balance. Native XLM has no
+    /// admin to mint from, so the balance is written into SAC storage.
+    fn setup_native(env: &Env, to: &Address, amount: i128) -> Address {
+        let native = env
+            .deployer()
+            .with_stellar_asset(Bytes::from_array(env, &[0u8; 4]))
+            .deploy();
+        let balance = SacBalance {
+            amount,
+            authorized: true,
+            clawback: false,
+        };
+        env.as_contract(&native, || {
+            env.storage()
+                .persistent()
+                .set(&SacKey::Balance(to.clone()), &balance)
+        });
+        native
+    }
+
+    #[test]
+    fn test_full_flow() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+
+        // Setup token and mint to owner
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &10_000);
+
+        // Init vault
+        client.init(&owner);
+        assert_eq!(client.owner(), owner);
+
+        // Deposit 5000
+        client.deposit(&owner, &token_addr, &5_000);
+        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+        assert_eq!(token_client.balance(&owner), 5_000);
+
+        // Withdraw 1000
+        client.withdraw(&owner, &token_addr, &1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 4_000);
+        assert_eq!(token_client.balance(&owner), 6_000);
+
+        // Lock 2000, expires at ledger 1000
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
+        assert_eq!(lock_id, 0);
+        assert_eq!(client.balance(&owner, &token_addr), 2_000);
+
+        // Verify lock entry
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.amount, 2_000);
+        assert_eq!(entry.status, LockStatus::Active);
+
+        // Release to recipient
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.status, LockStatus::Released);
+    }
+
+    #[test]
+    fn test_reclaim_expired() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &3_000);
+
+        // Lock expires at ledger 200
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &200);
+
+        // Advance past expiry
+        env.ledger().set_sequence_number(201);
+
+        // Reclaim expired funds
+        client.reclaim(&owner, &lock_id);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000); // 1000 remaining + 2000 reclaimed
+
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.status, LockStatus::Expired);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_withdraw_insufficient() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &100);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &100);
+        client.withdraw(&owner, &token_addr, &200); // panics: InsufficientFunds
+    }
+
+    #[test]
+    fn test_split_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &500);
+
+        // Split 400 off the 1000 lock
+        let new_id = client.split_lock(&owner, &lock_id, &400);
+        assert_eq!(new_id, 1);
+
+        let original = client.get_lock(&owner, &lock_id);
+        assert_eq!(original.amount, 600);
+        assert_eq!(original.status, LockStatus::Active);
+
+        let split = client.get_lock(&owner, &new_id);
+        assert_eq!(split.amount, 400);
+        assert_eq!(split.token, token_addr);
+        assert_eq!(split.expires_at, 500);
+        assert_eq!(split.status, LockStatus::Active);
+
+        // Cannot split off the whole lock
+        assert_eq!(
+            client.try_split_lock(&owner, &lock_id, &600),
+            Err(Ok(VaultError::InvalidAmount))
+        );
+    }
+
+    #[test]
+    fn test_event_level_none_suppresses_events() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        assert_eq!(client.event_level(), EVENTS_VERBOSE);
+
+        client.set_event_level(&EVENTS_NONE);
+
+        // The token contract still emits its transfer event; the vault must not
+        let vault_events = |env: &Env| {
+            env.events()
+                .all()
+                .iter()
+                .filter(|(addr, _, _)| *addr == contract_id)
+                .count()
+        };
+        let before = vault_events(&env);
+        client.deposit(&owner, &token_addr, &500);
+        assert_eq!(vault_events(&env), before);
+        assert_eq!(client.balance(&owner, &token_addr), 500);
+    }
+
+    /// Swaps at a fixed 2:1 rate out of its own pre-minted `token_out` balance.
+    #[contract]
+    struct MockAdapter;
+
+    #[contractimpl]
+    impl MockAdapter {
+        pub fn swap(
+            env: Env,
+            _token_in: Address,
+            token_out: Address,
+            amount_in: i128,
+            min_out: i128,
+            to: Address,
+        ) -> i128 {
+            let amount_out = amount_in * 2;
+            if amount_out < min_out {
+                panic!("slippage");
+            }
+            TokenClient::new(&env, &token_out).transfer(
+                &env.current_contract_address(),
+                &to,
+                &amount_out,
+            );
+            amount_out
+        }
+    }
+
+    #[test]
+    fn test_lock_swapped() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_a, _a_client, a_admin) = setup_token(&env, &admin);
+        let (token_b, b_client, b_admin) = setup_token(&env, &admin);
+        a_admin.mint(&owner, &1_000);
+
+        let adapter = env.register_contract(None, MockAdapter);
+        b_admin.mint(&adapter, &10_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_a, &1_000);
+        env.ledger().set_sequence_number(100);
+
+        // No adapter configured yet
+        assert_eq!(
+            client.try_lock_swapped(&owner, &token_a, &token_b, &300, &600, &500),
+            Err(Ok(VaultError::NoSwapAdapter))
+        );
+
+        client.set_swap_adapter(&adapter);
+        let lock_id = client.lock_swapped(&owner, &token_a, &token_b, &300, &600, &500);
+
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.token, token_b);
+        assert_eq!(entry.amount, 600);
+        assert_eq!(entry.status, LockStatus::Active);
+        assert_eq!(client.balance(&owner, &token_a), 700);
+        assert_eq!(b_client.balance(&contract_id), 600);
+
+        // Adapter failure reverts the whole call
+        assert_eq!(
+            client.try_lock_swapped(&owner, &token_a, &token_b, &100, &500, &500),
+            Err(Ok(VaultError::SwapFailed))
+        );
+        assert_eq!(client.balance(&owner, &token_a), 700);
+    }
+
+    #[test]
+    fn test_max_lock_duration() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+        assert_eq!(client.max_lock_duration(), None);
+
+        env.ledger().set_sequence_number(100);
+        client.set_max_lock_duration(&1_000);
+
+        // 1001 ledgers ahead is over the cap
+        assert_eq!(
+            client.try_lock(&owner, &token_addr, &500, &1_101),
+            Err(Ok(VaultError::LockTooLong))
+        );
+
+        // Exactly at the cap is fine
+        let lock_id = client.lock(&owner, &token_addr, &500, &1_100);
+        assert_eq!(client.get_lock(&owner, &lock_id).expires_at, 1_100);
+        assert_eq!(client.balance(&owner, &token_addr), 500);
+    }
+
+    #[test]
+    fn test_quote_release_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let vault_owner = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &2_000);
+
+        client.init(&vault_owner);
+        assert_eq!(client.quote_release_fee(&token_addr, &2_000), 0);
+
+        client.set_fee_bps(&250);
+        client.deposit(&owner, &token_addr, &2_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
+
+        // 2.5% of 2000
+        let quote = client.quote_release_fee(&token_addr, &2_000);
+        assert_eq!(quote, 50);
+
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(token_client.balance(&recipient), 2_000 - quote);
+        assert_eq!(client.balance(&vault_owner, &token_addr), quote);
+
+        // A token with an override is quoted at its own rate, as charged
+        let (other_addr, other_client, other_admin) = setup_token(&env, &admin);
+        other_admin.mint(&owner, &2_000);
+        client.set_token_fee(&other_addr, &1_000);
+        client.deposit(&owner, &other_addr, &2_000);
+        let lock_id = client.lock(&owner, &other_addr, &2_000, &1_000);
+
+        let quote = client.quote_release_fee(&other_addr, &2_000);
+        assert_eq!(quote, 200);
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(other_client.balance(&recipient), 2_000 - quote);
+        assert_eq!(client.balance(&vault_owner, &other_addr), quote);
+    }
+
+    #[test]
+    fn test_renew_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &200);
+
+        // Not auto-renew yet
+        env.ledger().set_sequence_number(201);
+        assert_eq!(
+            client.try_renew_lock(&owner, &lock_id),
+            Err(Ok(VaultError::NotAutoRenew))
+        );
+
+        client.set_auto_renew(&owner, &lock_id, &100);
+        let expires_at = client.renew_lock(&owner, &lock_id);
+        assert_eq!(expires_at, 300);
+
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.status, LockStatus::Active);
+        assert_eq!(entry.expires_at, 300);
+
+        // Active again, so it can be released but not renewed
+        assert_eq!(
+            client.try_renew_lock(&owner, &lock_id),
+            Err(Ok(VaultError::LockNotExpired))
+        );
+        let recipient = Address::generate(&env);
+        client.release(&owner, &lock_id, &recipient);
+    }
+
+    #[test]
+    fn test_solvency_matches_after_operations() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let vault_owner = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &10_000);
+
+        client.init(&vault_owner);
+        client.set_fee_bps(&100);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.withdraw(&owner, &token_addr, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let released = client.lock(&owner, &token_addr, &1_000, &200);
+        let reclaimed = client.lock(&owner, &token_addr, &500, &150);
+        client.lock(&owner, &token_addr, &700, &300);
+        client.release(&owner, &released, &recipient);
+
+        env.ledger().set_sequence_number(151);
+        client.reclaim(&owner, &reclaimed);
+
+        let (held, accounted) = client.solvency(&token_addr);
+        assert_eq!(held, accounted);
+        // 5000 in, 1000 withdrawn, 990 released net of the 10 fee
+        assert_eq!(held, 3_010);
+    }
+
+    #[test]
+    fn test_sweep_unaccounted() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let vault_owner = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let stray = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+        token_admin.mint(&stray, &300);
+
+        client.init(&vault_owner);
+        client.deposit(&owner, &token_addr, &1_000);
+        assert_eq!(client.sweep_unaccounted(&token_addr, &treasury), 0);
+
+        // Direct transfer bypassing deposit
+        token_client.transfer(&stray, &contract_id, &300);
+        assert_eq!(client.solvency(&token_addr), (1_300, 1_000));
+
+        assert_eq!(client.sweep_unaccounted(&token_addr, &treasury), 300);
+        assert_eq!(token_client.balance(&treasury), 300);
+        assert_eq!(client.solvency(&token_addr), (1_000, 1_000));
+        assert_eq!(client.balance(&owner, &token_addr), 1_000);
+    }
+
+    #[test]
+    fn test_list_locks_page_size_capped() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &10_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &10_000);
+        env.ledger().set_sequence_number(100);
+
+        let cap = client.max_page_size();
+        for _ in 0..cap + 5 {
+            client.lock(&owner, &token_addr, &10, &1_000);
+        }
+
+        assert_eq!(client.list_locks(&owner, &0, &(cap * 2)).len(), cap);
+
+        let rest = client.list_locks(&owner, &(cap as u64), &(cap * 2));
+        assert_eq!(rest.len(), 5);
+        assert_eq!(rest.get(0).unwrap().0, cap as u64);
+    }
+
+    #[test]
+    fn test_native_deposit_and_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let native = setup_native(&env, &owner, 1_000);
+        assert_eq!(client.native_token(), native);
+
+        client.init(&owner);
+        client.deposit_native(&owner, &700);
+        assert_eq!(client.balance(&owner, &native), 700);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock_native(&owner, &500, &1_000);
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.token, native);
+        assert_eq!(entry.amount, 500);
+
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(TokenClient::new(&env, &native).balance(&recipient), 500);
+    }
+
+    #[test]
+    fn test_delayed_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.set_withdraw_delay(&1_000, &50);
+
+        // Large withdrawals cannot go through the instant path
+        assert_eq!(
+            client.try_withdraw(&owner, &token_addr, &2_000),
+            Err(Ok(VaultError::WithdrawDelayed))
+        );
+
+        env.ledger().set_sequence_number(100);
+        let unlock_at = client.request_withdraw(&owner, &token_addr, &2_000);
+        assert_eq!(unlock_at, 150);
+
+        env.ledger().set_sequence_number(149);
+        assert_eq!(
+            client.try_execute_withdraw(&owner, &token_addr),
+            Err(Ok(VaultError::WithdrawNotReady))
+        );
+
+        env.ledger().set_sequence_number(150);
+        assert_eq!(client.execute_withdraw(&owner, &token_addr), 2_000);
+        assert_eq!(token_client.balance(&owner), 2_000);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+        assert!(client.pending_withdraw(&owner, &token_addr).is_none());
+    }
+
+    #[test]
+    fn test_small_withdraw_stays_instant() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.set_withdraw_delay(&1_000, &50);
+
+        // At the threshold is still instant
+        client.withdraw(&owner, &token_addr, &1_000);
+        assert_eq!(token_client.balance(&owner), 1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 4_000);
+    }
+
+    #[test]
+    fn test_cancel_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.set_withdraw_delay(&1_000, &50);
+
+        env.ledger().set_sequence_number(100);
+        client.request_withdraw(&owner, &token_addr, &3_000);
+        client.cancel_withdraw(&owner, &token_addr);
+
+        env.ledger().set_sequence_number(200);
+        assert_eq!(
+            client.try_execute_withdraw(&owner, &token_addr),
+            Err(Ok(VaultError::NoPendingWithdraw))
+        );
+        assert_eq!(
+            client.try_cancel_withdraw(&owner, &token_addr),
+            Err(Ok(VaultError::NoPendingWithdraw))
+        );
+        assert_eq!(token_client.balance(&owner), 0);
+        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+    }
+
+    #[test]
+    fn test_dispute_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&admin);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        let first = client.lock(&owner, &token_addr, &2_000, &1_000);
+        let second = client.lock(&owner, &token_addr, &1_000, &1_000);
+
+        client.dispute_lock(&owner, &first);
+        assert_eq!(client.get_lock(&owner, &first).status, LockStatus::Disputed);
+        assert_eq!(
+            client.try_release(&owner, &first, &recipient),
+            Err(Ok(VaultError::LockDisputed))
+        );
+
+        // Expiry does not unfreeze it
+        env.ledger().set_sequence_number(2_000);
+        assert_eq!(
+            client.try_reclaim(&owner, &first),
+            Err(Ok(VaultError::LockDisputed))
+        );
+
+        client.resolve_dispute(&owner, &first, &Some(recipient.clone()));
+        assert_eq!(client.get_lock(&owner, &first).status, LockStatus::Released);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+
+        // Resolving with no recipient returns funds to the owner's balance
+        client.dispute_lock(&owner, &second);
+        client.resolve_dispute(&owner, &second, &None);
+        assert_eq!(client.get_lock(&owner, &second).status, LockStatus::Expired);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+        assert_eq!(
+            client.try_resolve_dispute(&owner, &second, &None),
+            Err(Ok(VaultError::LockNotActive))
+        );
+        assert_eq!(client.solvency(&token_addr), (3_000, 3_000));
+    }
+
+    #[test]
+    fn test_next_expiry() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        assert_eq!(client.next_expiry(&owner), None);
+
+        env.ledger().set_sequence_number(100);
+        client.lock(&owner, &token_addr, &1_000, &900);
+        let earliest = client.lock(&owner, &token_addr, &1_000, &300);
+        client.lock(&owner, &token_addr, &1_000, &600);
+        assert_eq!(client.next_expiry(&owner), Some(300));
+
+        // Closed locks no longer count
+        client.release(&owner, &earliest, &recipient);
+        assert_eq!(client.next_expiry(&owner), Some(600));
+    }
+
+    #[test]
+    fn test_withdraw_from_allowance() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        client.approve(&owner, &spender, &token_addr, &1_500);
+        assert_eq!(client.allowance(&owner, &spender, &token_addr), 1_500);
+
+        client.withdraw_from(&spender, &owner, &token_addr, &1_000);
+        assert_eq!(token_client.balance(&spender), 1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 4_000);
+        assert_eq!(client.allowance(&owner, &spender, &token_addr), 500);
+
+        assert_eq!(
+            client.try_withdraw_from(&spender, &owner, &token_addr, &501),
+            Err(Ok(VaultError::InsufficientAllowance))
+        );
+        assert_eq!(client.balance(&owner, &token_addr), 4_000);
+    }
+
+    #[test]
+    fn test_lock_with_metadata() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+
+        let metadata = Bytes::from_slice(&env, b"{\"order\":42}");
+        let lock_id = client.lock_with_metadata(&owner, &token_addr, &1_000, &500, &metadata);
+        assert_eq!(client.get_lock(&owner, &lock_id).metadata, metadata);
+
+        let plain = client.lock(&owner, &token_addr, &1_000, &500);
+        assert!(client.get_lock(&owner, &plain).metadata.is_empty());
+
+        let too_long = Bytes::from_array(&env, &[0u8; MAX_METADATA_LEN as usize + 1]);
+        assert_eq!(
+            client.try_lock_with_metadata(&owner, &token_addr, &1_000, &500, &too_long),
+            Err(Ok(VaultError::MetadataTooLong))
+        );
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+    }
+
+    #[test]
+    fn test_incoming_locks() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&alice, &5_000);
+        token_admin.mint(&bob, &5_000);
+
+        client.init(&admin);
+        client.deposit(&alice, &token_addr, &5_000);
+        client.deposit(&bob, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+
+        let a_id = client.lock_to(&alice, &token_addr, &1_000, &500, &payee);
+        let b_id = client.lock_to(&bob, &token_addr, &2_500, &500, &payee);
+        client.lock(&alice, &token_addr, &700, &500);
+        assert_eq!(client.get_lock(&alice, &a_id).beneficiary, Some(payee.clone()));
+
+        let incoming = client.incoming_locks(&payee);
+        assert_eq!(incoming.len(), 2);
+        assert_eq!(incoming.get(0).unwrap(), (alice.clone(), a_id, 1_000));
+        assert_eq!(incoming.get(1).unwrap(), (bob.clone(), b_id, 2_500));
+
+        client.release(&alice, &a_id, &payee);
+        assert_eq!(token_client.balance(&payee), 1_000);
+        let incoming = client.incoming_locks(&payee);
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming.get(0).unwrap(), (bob, b_id, 2_500));
+    }
+
+    #[test]
+    fn test_lock_from_allowance() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        env.ledger().set_sequence_number(100);
+        token_client.approve(&owner, &contract_id, &2_000, &1_000);
+
+        let lock_id = client.lock_from_allowance(&owner, &token_addr, &1_500, &500, &recipient);
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.amount, 1_500);
+        assert_eq!(entry.beneficiary, Some(recipient.clone()));
+
+        assert_eq!(token_client.balance(&owner), 3_500);
+        assert_eq!(token_client.allowance(&owner, &contract_id), 500);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+        assert_eq!(client.solvency(&token_addr), (1_500, 1_500));
+
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(token_client.balance(&recipient), 1_500);
+    }
+
+    #[test]
+    fn test_reclaim_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+
+        let a = client.lock(&owner, &token_addr, &1_000, &200);
+        let b = client.lock(&owner, &token_addr, &700, &300);
+        let c = client.lock(&owner, &token_addr, &500, &900);
+        assert_eq!(client.balance(&owner, &token_addr), 2_800);
+
+        env.ledger().set_sequence_number(400);
+        let before = env.events().all().len();
+        let mut ids = Vec::new(&env);
+        ids.push_back(a);
+        ids.push_back(b);
+        ids.push_back(c);
+        ids.push_back(99);
+        assert_eq!(client.reclaim_batch(&owner, &ids), 1_700);
+        assert_eq!(env.events().all().len() - before, 2);
+
+        assert_eq!(client.balance(&owner, &token_addr), 4_500);
+        assert_eq!(client.get_lock(&owner, &a).status, LockStatus::Expired);
+        assert_eq!(client.get_lock(&owner, &b).status, LockStatus::Expired);
+        assert_eq!(client.get_lock(&owner, &c).status, LockStatus::Active);
+
+        // Already reclaimed locks are skipped
+        assert_eq!(client.reclaim_batch(&owner, &ids), 0);
+    }
+
+    #[test]
+    fn test_expire_on_read() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &200);
+
+        env.ledger().set_sequence_number(300);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+
+        client.set_expire_on_read(&true);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Expired);
+        let page = client.list_locks(&owner, &0, &10);
+        assert_eq!(page.get(0).unwrap().1.status, LockStatus::Expired);
+
+        // Nothing was written: the lock can still be reclaimed
+        client.set_expire_on_read(&false);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+        client.reclaim(&owner, &lock_id);
+        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+    }
+
+    #[test]
+    fn test_min_deposit_rejects_dust() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.set_min_deposit(&token_addr, &100);
+        assert_eq!(client.min_deposit(&token_addr), 100);
+
+        assert_eq!(
+            client.try_deposit(&owner, &token_addr, &99),
+            Err(Ok(VaultError::BelowMinDeposit))
+        );
+        assert_eq!(token_client.balance(&owner), 5_000);
+    }
+
+    #[test]
+    fn test_min_deposit_accepts_minimum() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.set_min_deposit(&token_addr, &100);
+        client.deposit(&owner, &token_addr, &100);
+        assert_eq!(client.balance(&owner, &token_addr), 100);
+
+        // Clearing the minimum allows small deposits again
+        client.set_min_deposit(&token_addr, &0);
+        client.deposit(&owner, &token_addr, &1);
+        assert_eq!(client.balance(&owner, &token_addr), 101);
+    }
+
+    #[test]
+    fn test_deposit_cap() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.set_deposit_cap(&token_addr, &3_000);
+        assert_eq!(client.deposit_cap(&token_addr), Some(3_000));
+
+        client.deposit(&owner, &token_addr, &2_000);
+        client.deposit(&owner, &token_addr, &1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+
+        assert_eq!(
+            client.try_deposit(&owner, &token_addr, &1),
+            Err(Ok(VaultError::CapExceeded))
+        );
+        assert_eq!(token_client.balance(&owner), 2_000);
+    }
+
+    #[test]
+    fn test_is_releasable() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &200);
+
+        assert!(client.is_releasable(&owner, &lock_id));
+        env.ledger().set_sequence_number(200);
+        assert!(client.is_releasable(&owner, &lock_id));
+        env.ledger().set_sequence_number(201);
+        assert!(!client.is_releasable(&owner, &lock_id));
+        assert!(!client.is_releasable(&owner, &99));
+    }
+
+    #[test]
+    fn test_bump_locks() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+
+        let mut ids = Vec::new(&env);
+        ids.push_back(client.lock(&owner, &token_addr, &1_000, &500));
+        ids.push_back(client.lock(&owner, &token_addr, &1_000, &600));
+        ids.push_back(42);
+
+        assert_eq!(client.bump_locks(&owner, &ids, &u32::MAX), 2);
+        env.as_contract(&contract_id, || {
+            let key = DataKey::Lock(owner.clone(), 1);
+            assert_eq!(
+                env.storage().persistent().get_ttl(&key),
+                env.storage().max_ttl()
+            );
+        });
+    }
+
+    #[test]
+    fn test_token_fee_override() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let vault_owner = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (usdc, usdc_client, usdc_admin) = setup_token(&env, &admin);
+        let (other, other_client, other_admin) = setup_token(&env, &admin);
+        usdc_admin.mint(&owner, &2_000);
+        other_admin.mint(&owner, &2_000);
+
+        client.init(&vault_owner);
+        client.set_fee_bps(&250);
+        client.set_token_fee(&usdc, &50);
+        assert_eq!(client.token_fee_bps(&usdc), 50);
+        assert_eq!(client.token_fee_bps(&other), 250);
+
+        client.deposit(&owner, &usdc, &2_000);
+        client.deposit(&owner, &other, &2_000);
+        env.ledger().set_sequence_number(100);
+        let usdc_lock = client.lock(&owner, &usdc, &2_000, &1_000);
+        let other_lock = client.lock(&owner, &other, &2_000, &1_000);
+
+        // 0.5% override vs 2.5% global
+        client.release(&owner, &usdc_lock, &recipient);
+        assert_eq!(usdc_client.balance(&recipient), 1_990);
+        assert_eq!(client.balance(&vault_owner, &usdc), 10);
+
+        client.release(&owner, &other_lock, &recipient);
+        assert_eq!(other_client.balance(&recipient), 1_950);
+        assert_eq!(client.balance(&vault_owner, &other), 50);
+
+        client.clear_token_fee(&usdc);
+        assert_eq!(client.token_fee_bps(&usdc), 250);
+    }
+
+    #[test]
+    fn test_offer_and_accept_release() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
+
+        client.offer_release(&owner, &lock_id, &recipient);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Offered);
+        assert_eq!(token_client.balance(&recipient), 0);
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &stranger),
+            Err(Ok(VaultError::LockNotActive))
+        );
+        assert_eq!(
+            client.try_accept_release(&stranger, &owner, &lock_id),
+            Err(Ok(VaultError::NotRecipient))
+        );
+
+        client.accept_release(&recipient, &owner, &lock_id);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Released);
+    }
+
+    #[test]
+    fn test_offer_and_rescind() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
+
+        client.offer_release(&owner, &lock_id, &recipient);
+        client.rescind_offer(&owner, &lock_id);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+        assert_eq!(
+            client.try_accept_release(&recipient, &owner, &lock_id),
+            Err(Ok(VaultError::LockNotActive))
+        );
+
+        // Back to normal: the owner can release elsewhere
+        client.release(&owner, &lock_id, &owner);
+        assert_eq!(token_client.balance(&owner), 2_000);
+        assert_eq!(token_client.balance(&recipient), 0);
+    }
+
+    #[test]
+    fn test_spendable_excludes_pending_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.set_withdraw_delay(&1_000, &50);
+        assert_eq!(client.spendable(&owner, &token_addr), 5_000);
+
+        env.ledger().set_sequence_number(100);
+        client.request_withdraw(&owner, &token_addr, &3_000);
+        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+        assert_eq!(client.spendable(&owner, &token_addr), 2_000);
+
+        // Locking past the pending amount drives spendable to the floor
+        client.lock(&owner, &token_addr, &4_000, &500);
+        assert_eq!(client.spendable(&owner, &token_addr), 0);
+    }
+
+    #[test]
+    fn test_transfer_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let new_owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&admin);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock_to(&owner, &token_addr, &2_000, &1_000, &recipient);
+
+        client.transfer_lock(&owner, &lock_id, &new_owner);
+        assert_eq!(
+            client.try_get_lock(&owner, &lock_id).err(),
+            Some(Ok(VaultError::LockNotFound))
+        );
+        let page = client.list_locks(&new_owner, &0, &10);
+        assert_eq!(page.len(), 1);
+        let (new_id, entry) = page.get(0).unwrap();
+        assert_eq!(entry.amount, 2_000);
+        assert_eq!(
+            client.incoming_locks(&recipient).get(0).unwrap(),
+            (new_owner.clone(), new_id, 2_000)
+        );
+
+        // The previous owner has no say any more; the new one releases
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &owner),
+            Err(Ok(VaultError::LockNotFound))
+        );
+        client.release(&new_owner, &new_id, &recipient);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+        assert_eq!(client.solvency(&token_addr), (3_000, 3_000));
+    }
+
+    #[test]
+    fn test_keeper_settles_to_beneficiary() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&admin);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let to_payee = client.lock_to(&owner, &token_addr, &2_000, &200, &payee);
+        let plain = client.lock(&owner, &token_addr, &1_000, &200);
+
+        assert_eq!(
+            client.try_settle(&keeper, &owner, &to_payee),
+            Err(Ok(VaultError::LockNotExpired))
+        );
+
+        env.ledger().set_sequence_number(201);
+        client.settle(&keeper, &owner, &to_payee);
+        assert_eq!(token_client.balance(&payee), 2_000);
+        assert_eq!(client.get_lock(&owner, &to_payee).status, LockStatus::Released);
+        assert_eq!(client.balance(&owner, &token_addr), 2_000);
+        assert_eq!(client.incoming_locks(&payee).len(), 0);
+
+        // Without a beneficiary, settling returns funds to the owner
+        client.settle(&keeper, &owner, &plain);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+        assert_eq!(client.get_lock(&owner, &plain).status, LockStatus::Expired);
+        assert_eq!(client.solvency(&token_addr), (3_000, 3_000));
+    }
+
+    #[test]
+    fn test_withdraw_rate_limit() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        client.set_withdraw_limit(&1_000, &100);
+
+        // Within the limit
+        env.ledger().set_sequence_number(100);
+        client.withdraw(&owner, &token_addr, &600);
+        client.withdraw(&owner, &token_addr, &400);
+        assert_eq!(token_client.balance(&owner), 1_000);
+
+        // Over the limit in the same window
+        env.ledger().set_sequence_number(199);
+        assert_eq!(
+            client.try_withdraw(&owner, &token_addr, &1),
+            Err(Ok(VaultError::RateLimited))
+        );
+
+        // A new window resets the allowance
+        env.ledger().set_sequence_number(200);
+        client.withdraw(&owner, &token_addr, &1_000);
+        assert_eq!(token_client.balance(&owner), 2_000);
+        assert_eq!(client.balance(&owner, &token_addr), 3_000);
+    }
+
+    #[test]
+    fn test_lock_count() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        assert_eq!(client.lock_count(&owner), 0);
+
+        env.ledger().set_sequence_number(100);
+        let first = client.lock(&owner, &token_addr, &2_000, &500);
+        client.lock(&owner, &token_addr, &1_000, &500);
+        client.split_lock(&owner, &first, &500);
+        client.release(&owner, &first, &recipient);
+        assert_eq!(client.lock_count(&owner), 3);
+        assert_eq!(client.list_locks(&owner, &0, &10).len(), 3);
+    }
+
+    #[test]
+    fn test_lock_basket() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_a, client_a, admin_a) = setup_token(&env, &admin);
+        let (token_b, client_b, admin_b) = setup_token(&env, &admin);
+        admin_a.mint(&owner, &1_000);
+        admin_b.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_a, &1_000);
+        client.deposit(&owner, &token_b, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let items = Vec::from_array(
+            &env,
+            [(token_a.clone(), 400_i128), (token_b.clone(), 700_i128)],
+        );
+        let basket_id = client.lock_basket(&owner, &items, &500, &recipient);
+        assert_eq!(basket_id, 0);
+        assert_eq!(client.balance(&owner, &token_a), 600);
+        assert_eq!(client.balance(&owner, &token_b), 300);
+        assert_eq!(client.solvency(&token_a), (1_000, 1_000));
+
+        // A basket the owner can't fully fund locks nothing
+        let too_much = Vec::from_array(
+            &env,
+            [(token_a.clone(), 100_i128), (token_b.clone(), 301_i128)],
+        );
+        assert_eq!(
+            client.try_lock_basket(&owner, &too_much, &500, &recipient),
+            Err(Ok(VaultError::InsufficientFunds))
+        );
+
+        client.release_basket(&owner, &basket_id);
+        assert_eq!(client_a.balance(&recipient), 400);
+        assert_eq!(client_b.balance(&recipient), 700);
+        assert_eq!(client.get_basket(&owner, &basket_id).status, LockStatus::Released);
+        assert_eq!(client.solvency(&token_b), (300, 300));
+        assert_eq!(
+            client.try_reclaim_basket(&owner, &basket_id),
+            Err(Ok(VaultError::LockNotActive))
+        );
+    }
+
+    #[test]
+    fn test_check_invariants_flags_unswept_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Keep the token contract's entries live across the grace period
+        env.ledger().with_mut(|li| {
+            li.min_persistent_entry_ttl = 100_000;
+            li.min_temp_entry_ttl = 100_000;
+            li.max_entry_ttl = 1_000_000;
+        });
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &500);
+        assert!(client.check_invariants(&owner, &token_addr));
+
+        // Lazily expired but within grace: still healthy
+        env.ledger().set_sequence_number(501);
+        assert!(client.check_invariants(&owner, &token_addr));
+
+        // Left unswept past the grace period
+        env.ledger().set_sequence_number((500 + EXPIRY_GRACE + 1) as u32);
+        assert!(!client.check_invariants(&owner, &token_addr));
+
+        client.reclaim(&owner, &lock_id);
+        assert!(client.check_invariants(&owner, &token_addr));
+    }
+
+    #[test]
+    fn test_lock_summary() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let beneficiary = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock_to(&owner, &token_addr, &1_500, &500, &beneficiary);
+
+        assert_eq!(
+            client.lock_summary(&owner, &lock_id),
+            LockSummary {
+                token: token_addr,
+                amount: 1_500,
+                expires_at: 500,
+                status: LockStatus::Active,
+                beneficiary: Some(beneficiary),
+            }
+        );
+        assert_eq!(
+            client.try_lock_summary(&owner, &(lock_id + 1)),
+            Err(Ok(VaultError::LockNotFound))
+        );
+    }
+
+    #[test]
+    fn test_extend_lock_paid() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payer = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+        token_admin.mint(&payer, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &500);
+        assert_eq!(
+            client.try_extend_lock_paid(&payer, &owner, &lock_id, &900),
+            Err(Ok(VaultError::ExtensionDisabled))
+        );
+
+        client.set_extension_fee(&250);
+        token_client.approve(&payer, &contract_id, &250, &1_000);
+        client.extend_lock_paid(&payer, &owner, &lock_id, &900);
+
+        assert_eq!(client.get_lock(&owner, &lock_id).expires_at, 900);
+        assert_eq!(token_client.balance(&payer), 750);
+        assert_eq!(client.balance(&owner, &token_addr), 3_250);
+        let (held, accounted) = client.solvency(&token_addr);
+        assert_eq!(held, accounted);
+
+        // Extensions only move expiry forward
+        assert_eq!(
+            client.try_extend_lock_paid(&payer, &owner, &lock_id, &800),
+            Err(Ok(VaultError::InvalidExpiry))
+        );
+    }
+
+    #[test]
+    fn test_get_locks() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        client.lock(&owner, &token_addr, &1_000, &500);
+        client.lock(&owner, &token_addr, &2_000, &500);
+
+        let ids = Vec::from_array(&env, [1u64, 7, 0]);
+        let entries = client.get_locks(&owner, &ids);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.get(0).unwrap().unwrap().amount, 2_000);
+        assert!(entries.get(1).unwrap().is_none());
+        assert_eq!(entries.get(2).unwrap().unwrap().amount, 1_000);
+
+        let mut many = Vec::new(&env);
+        for i in 0..(MAX_PAGE_SIZE as u64 + 10) {
+            many.push_back(i);
+        }
+        assert_eq!(client.get_locks(&owner, &many).len(), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_require_memo_rejects_plain_deposit() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.set_require_memo(&true);
+        assert!(client.require_memo());
+
+        assert_eq!(
+            client.try_deposit(&owner, &token_addr, &500),
+            Err(Ok(VaultError::MemoRequired))
+        );
+        assert_eq!(
+            client.try_deposit_with_memo(&owner, &token_addr, &500, &Bytes::new(&env)),
+            Err(Ok(VaultError::MemoRequired))
+        );
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+    }
+
+    #[test]
+    fn test_deposit_with_memo() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.set_require_memo(&true);
+
+        let memo = Bytes::from_slice(&env, b"exchange-ref-42");
+        client.deposit_with_memo(&owner, &token_addr, &500, &memo);
+        assert_eq!(client.balance(&owner, &token_addr), 500);
+        assert_eq!(token_client.balance(&owner), 500);
+    }
+
+    /// Token whose outgoing transfers fail once `set_fail(true)` is called.
+    #[contract]
+    struct FailingToken;
+
+    #[contractimpl]
+    impl FailingToken {
+        pub fn set_fail(env: Env, fail: bool) {
+            env.storage().instance().set(&Symbol::new(&env, "fail"), &fail);
+        }
+
+        pub fn transfer(env: Env, _from: Address, _to: Address, _amount: i128) {
+            if env.storage().instance().get(&Symbol::new(&env, "fail")).unwrap_or(false) {
+                panic!("transfer rejected");
+            }
+        }
+    }
+
+    #[test]
+    fn test_release_transfer_failure_keeps_lock_active() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let token_addr = env.register_contract(None, FailingToken);
+        let token = FailingTokenClient::new(&env, &token_addr);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &600, &500);
+
+        token.set_fail(&true);
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &recipient),
+            Err(Ok(VaultError::TransferFailed))
+        );
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+
+        token.set_fail(&false);
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Released);
+    }
+
+    #[test]
+    fn test_closed_locks() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        let released = client.lock(&owner, &token_addr, &100, &500);
+        client.lock(&owner, &token_addr, &100, &900);
+        let reclaimed = client.lock(&owner, &token_addr, &100, &200);
+        let released_too = client.lock(&owner, &token_addr, &100, &500);
+
+        client.release(&owner, &released, &recipient);
+        client.release(&owner, &released_too, &recipient);
+        env.ledger().set_sequence_number(300);
+        client.reclaim(&owner, &reclaimed);
+
+        let closed = client.closed_locks(&owner, &0, &10);
+        assert_eq!(closed.len(), 3);
+        assert_eq!(closed.get(0).unwrap().0, released);
+        assert_eq!(closed.get(1).unwrap().1.status, LockStatus::Expired);
+        assert_eq!(closed.get(2).unwrap().0, released_too);
+
+        let page = client.closed_locks(&owner, &0, &2);
+        assert_eq!(page.len(), 2);
+        let rest = client.closed_locks(&owner, &(page.get(1).unwrap().0 + 1), &2);
+        assert_eq!(rest.len(), 1);
+    }
+
+    #[test]
+    fn test_deposit_and_extend() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        let (other_token, _, other_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+        other_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &500);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+
+        client.deposit_and_extend(&owner, &token_addr, &800, &lock_id, &600);
+        assert_eq!(client.get_lock(&owner, &lock_id).amount, 1_600);
+        assert_eq!(client.balance(&owner, &token_addr), 200);
+        assert_eq!(client.solvency(&token_addr), (1_800, 1_800));
+
+        assert_eq!(
+            client.try_deposit_and_extend(&owner, &other_token, &500, &lock_id, &500),
+            Err(Ok(VaultError::TokenMismatch))
+        );
+    }
+
+    #[test]
+    fn test_lock_default() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(100);
+        assert_eq!(
+            client.try_lock_default(&owner, &token_addr, &1_000, &recipient),
+            Err(Ok(VaultError::NoDefaultDuration))
+        );
+
+        client.set_default_lock_duration(&720);
+        let lock_id = client.lock_default(&owner, &token_addr, &1_000, &recipient);
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.expires_at, 820);
+        assert_eq!(entry.beneficiary, Some(recipient));
+        assert_eq!(client.balance(&owner, &token_addr), 4_000);
+    }
+
+    #[test]
+    fn test_lock_to_event_carries_beneficiary() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let beneficiary = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+
+        let topics = (Symbol::new(&env, "lock"), owner.clone(), token_addr.clone()).into_val(&env);
+
+        let plain = client.lock(&owner, &token_addr, &1_000, &500);
+        let event = env.events().all().last().unwrap();
+        assert_eq!(event.1, topics);
+        let data: (u64, i128, u64) = event.2.into_val(&env);
+        assert_eq!(data, (plain, 1_000, 500));
+
+        let bound = client.lock_to(&owner, &token_addr, &2_000, &600, &beneficiary);
+        let event = env.events().all().last().unwrap();
+        assert_eq!(event.1, topics);
+        let data: (u64, i128, u64, Address) = event.2.into_val(&env);
+        assert_eq!(data, (bound, 2_000, 600, beneficiary));
+    }
+
+    #[test]
+    fn test_withdraw_auto_dust() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.set_min_deposit(&token_addr, &50);
+        client.set_auto_dust(&true);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        // Leaves 500, above the dust threshold: plain partial withdrawal
+        client.withdraw(&owner, &token_addr, &500);
+        assert_eq!(client.balance(&owner, &token_addr), 500);
+
+        // The large-withdraw threshold counts the swept remainder: 480 alone
+        // is under it, the 500 actually sent is not
+        client.set_withdraw_delay(&490, &10);
+        assert_eq!(
+            client.try_withdraw(&owner, &token_addr, &480),
+            Err(Ok(VaultError::WithdrawDelayed))
+        );
+        client.set_withdraw_delay(&490, &0);
+
+        // Leaves 20 < 50: the remainder goes out too and the entry is removed
+        client.withdraw(&owner, &token_addr, &480);
+        assert_eq!(token_client.balance(&owner), 1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+        let key = DataKey::Balance(owner.clone(), token_addr.clone());
+        env.as_contract(&contract_id, || {
+            assert!(!env.storage().persistent().has(&key));
+        });
+        assert_eq!(client.solvency(&token_addr), (0, 0));
+    }
+
+    #[test]
+    fn test_freeze_self() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &2_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &token_addr, &500, &500);
+
+        client.freeze_self(&owner);
+        assert!(client.is_self_frozen(&owner));
+
+        assert_eq!(
+            client.try_withdraw(&owner, &token_addr, &100),
+            Err(Ok(VaultError::AccountFrozen))
+        );
+        assert_eq!(
+            client.try_lock(&owner, &token_addr, &100, &500),
+            Err(Ok(VaultError::AccountFrozen))
+        );
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &recipient),
+            Err(Ok(VaultError::AccountFrozen))
+        );
+
+        // Deposits still go through
+        client.deposit(&owner, &token_addr, &1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 2_500);
+    }
+
+    #[test]
+    fn test_unfreeze_self() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+
+        client.freeze_self(&owner);
+        client.unfreeze_self(&owner);
+        assert!(!client.is_self_frozen(&owner));
+
+        client.withdraw(&owner, &token_addr, &400);
+        assert_eq!(token_client.balance(&owner), 400);
+    }
+
+    #[test]
+    fn test_lock_xdr_round_trip() {
+        use soroban_sdk::xdr::FromXdr;
+
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let beneficiary = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &5_000);
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock_to(&owner, &token_addr, &1_500, &500, &beneficiary);
+
+        let xdr = client.lock_xdr(&owner, &lock_id);
+        let decoded = LockEntry::from_xdr(&env, &xdr).unwrap();
+        assert_eq!(decoded.token, token_addr);
+        assert_eq!(decoded.amount, 1_500);
+        assert_eq!(decoded.expires_at, 500);
+        assert_eq!(decoded.status, LockStatus::Active);
+        assert_eq!(decoded.beneficiary, Some(beneficiary));
+        assert_eq!(decoded.to_xdr(&env), xdr);
+        assert_eq!(
+            client.try_lock_xdr(&owner, &(lock_id + 1)),
+            Err(Ok(VaultError::LockNotFound))
+        );
+    }
+
+    #[test]
+    fn test_challenge_reclaim_blocks_reclaim() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.set_challenge_window(&100);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(10);
+        let lock_id = client.lock_to(&owner, &token_addr, &1_000, &200, &payee);
+
+        // Before expiry there is nothing to challenge
+        assert_eq!(
+            client.try_challenge_reclaim(&owner, &lock_id),
+            Err(Ok(VaultError::LockNotExpired))
+        );
+
+        // Expired, but the owner must wait out the window
+        env.ledger().set_sequence_number(250);
+        assert_eq!(
+            client.try_reclaim(&owner, &lock_id),
+            Err(Ok(VaultError::ChallengePeriod))
+        );
+
+        client.challenge_reclaim(&owner, &lock_id);
+        assert_eq!(env.auths()[0].0, payee);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Disputed);
+        assert_eq!(client.challenge(&owner, &lock_id), Some(250));
+
+        env.ledger().set_sequence_number(400);
+        assert_eq!(
+            client.try_reclaim(&owner, &lock_id),
+            Err(Ok(VaultError::LockDisputed))
+        );
+
+        client.resolve_dispute(&owner, &lock_id, &Some(payee.clone()));
+        assert_eq!(token_client.balance(&payee), 1_000);
+        assert_eq!(client.challenge(&owner, &lock_id), None);
+    }
+
+    #[test]
+    fn test_late_challenge_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&owner);
+        client.set_challenge_window(&100);
+        client.deposit(&owner, &token_addr, &5_000);
+
+        env.ledger().set_sequence_number(10);
+        let lock_id = client.lock_to(&owner, &token_addr, &1_000, &200, &payee);
+
+        env.ledger().set_sequence_number(301);
+        assert_eq!(
+            client.try_challenge_reclaim(&owner, &lock_id),
+            Err(Ok(VaultError::ChallengeClosed))
+        );
+
+        client.reclaim(&owner, &lock_id);
+        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+    }
+
+    #[test]
+    fn test_recorrect_lock_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (wrong, _, wrong_admin) = setup_token(&env, &admin);
+        let (right, right_client, right_admin) = setup_token(&env, &admin);
+        wrong_admin.mint(&owner, &1_000);
+        right_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &wrong, &1_000);
+        client.deposit(&owner, &right, &1_000);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock(&owner, &wrong, &700, &500);
+
+        client.recorrect_lock_token(&owner, &lock_id, &right);
+        let entry = client.get_lock(&owner, &lock_id);
+        assert_eq!(entry.token, right);
+        assert_eq!(entry.amount, 700);
+        assert_eq!(client.balance(&owner, &wrong), 1_000);
+        assert_eq!(client.balance(&owner, &right), 300);
+        assert_eq!(client.solvency(&wrong), (1_000, 1_000));
+        assert_eq!(client.solvency(&right), (1_000, 1_000));
+
+        client.release(&owner, &lock_id, &recipient);
+        assert_eq!(right_client.balance(&recipient), 700);
+    }
+
+    #[test]
+    fn test_release_with_interest() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let sponsor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &100_000);
+        token_admin.mint(&sponsor, &5_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &100_000);
 
-    fn setup_token(env: &Env, admin: &Address) -> (Address, TokenClient, StellarAssetClient) {
-        let addr = env.register_stellar_asset_contract_v2(admin.clone()).address();
-        let client = TokenClient::new(env, &addr);
-        let admin_client = StellarAssetClient::new(env, &addr);
-        (addr, client, admin_client)
+        env.ledger().set_sequence_number(100);
+        // Interest-bearing locks are off until the admin sets a cap
+        assert_eq!(
+            client.try_lock_with_interest(&owner, &token_addr, &100_000, &10_000, &50),
+            Err(Ok(VaultError::RateTooHigh))
+        );
+        client.set_max_interest_rate(&token_addr, &50);
+        assert_eq!(client.max_interest_rate(&token_addr), 50);
+        assert_eq!(
+            client.try_lock_with_interest(&owner, &token_addr, &100_000, &10_000, &51),
+            Err(Ok(VaultError::RateTooHigh))
+        );
+
+        // 50 bps per 1000 ledgers
+        let lock_id = client.lock_with_interest(&owner, &token_addr, &100_000, &10_000, &50);
+
+        env.ledger().set_sequence_number(4_100);
+        // 100_000 * 50 * 4_000 / 10_000_000
+        assert_eq!(client.accrued_interest(&owner, &lock_id), 2_000);
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &recipient),
+            Err(Ok(VaultError::ReserveInsufficient))
+        );
+
+        client.fund_reserve(&sponsor, &token_addr, &5_000);
+        client.release(&owner, &lock_id, &recipient);
+
+        assert_eq!(token_client.balance(&recipient), 102_000);
+        assert_eq!(client.reserve(&token_addr), 3_000);
+        let (held, accounted) = client.solvency(&token_addr);
+        assert_eq!(held, accounted);
     }
 
     #[test]
-    fn test_full_flow() {
+    fn test_unique_owners() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        let (other_token, _, other_admin) = setup_token(&env, &admin);
+        token_admin.mint(&alice, &1_000);
+        token_admin.mint(&bob, &1_000);
+        other_admin.mint(&alice, &1_000);
+
+        client.init(&alice);
+        assert_eq!(client.unique_owners(), 0);
+
+        client.deposit(&alice, &token_addr, &500);
+        client.deposit(&bob, &token_addr, &500);
+        assert_eq!(client.unique_owners(), 2);
+
+        // Repeat deposits, in any token, don't count again
+        client.deposit(&alice, &token_addr, &500);
+        client.deposit(&alice, &other_token, &500);
+        assert_eq!(client.unique_owners(), 2);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        assert!(client.is_empty(&owner));
+
+        client.deposit(&owner, &token_addr, &500);
+        assert!(!client.is_empty(&owner));
+
+        // Fully locked funds still count as holdings, offered or disputed
+        let lock_id = client.lock(&owner, &token_addr, &500, &100);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+        assert!(!client.is_empty(&owner));
+        client.offer_release(&owner, &lock_id, &admin);
+        assert!(!client.is_empty(&owner));
+        client.rescind_offer(&owner, &lock_id);
+        client.dispute_lock(&owner, &lock_id);
+        assert!(!client.is_empty(&owner));
+
+        // So does a basket
+        let basket_owner = Address::generate(&env);
+        token_admin.mint(&basket_owner, &500);
+        client.deposit(&basket_owner, &token_addr, &500);
+        let items = Vec::from_array(&env, [(token_addr.clone(), 500)]);
+        client.lock_basket(&basket_owner, &items, &100, &admin);
+        assert_eq!(client.balance(&basket_owner, &token_addr), 0);
+        assert!(!client.is_empty(&basket_owner));
+    }
+
+    #[test]
+    fn test_token_stats() {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(EscrowVault, ());
+        let contract_id = env.register_contract(None, EscrowVault);
         let client = EscrowVaultClient::new(&env, &contract_id);
 
+        let vault_owner = Address::generate(&env);
         let owner = Address::generate(&env);
         let recipient = Address::generate(&env);
         let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &3_000);
 
-        // Setup token and mint to owner
-        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
-        token_admin.mint(&owner, &10_000);
+        client.init(&vault_owner);
+        client.set_fee_bps(&250);
+        client.deposit(&owner, &token_addr, &3_000);
+        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
 
-        // Init vault
-        client.init(&owner);
-        assert_eq!(client.owner(), owner);
+        let stats = client.token_stats(&token_addr);
+        assert_eq!(
+            stats,
+            TokenStats {
+                total_deposited: 1_000,
+                total_locked: 2_000,
+                accrued_fees: 0,
+                actual_balance: 3_000,
+            }
+        );
 
-        // Deposit 5000
+        // 2.5% of 2000 stays in the vault as the owner's fee balance
+        client.release(&owner, &lock_id, &recipient);
+        let stats = client.token_stats(&token_addr);
+        assert_eq!(
+            stats,
+            TokenStats {
+                total_deposited: 1_050,
+                total_locked: 0,
+                accrued_fees: 50,
+                actual_balance: 1_050,
+            }
+        );
+    }
+
+    #[test]
+    fn test_emergency_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let vault_owner = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &5_000);
+
+        client.init(&vault_owner);
         client.deposit(&owner, &token_addr, &5_000);
-        assert_eq!(client.balance(&owner, &token_addr), 5_000);
+        let plain = client.lock(&owner, &token_addr, &1_000, &1_000);
+        let to_payee = client.lock_to(&owner, &token_addr, &2_000, &1_000, &payee);
+
+        assert_eq!(
+            client.try_emergency_withdraw(&owner, &token_addr),
+            Err(Ok(VaultError::NotEmergency))
+        );
+
+        client.set_emergency_at(&50);
+        env.ledger().set_sequence_number(50);
+
+        // A self-frozen account stays frozen even during wind-down
+        client.freeze_self(&owner);
+        assert_eq!(
+            client.try_emergency_withdraw(&owner, &token_addr),
+            Err(Ok(VaultError::AccountFrozen))
+        );
+        client.unfreeze_self(&owner);
+
+        assert_eq!(client.emergency_withdraw(&owner, &token_addr), 5_000);
+
         assert_eq!(token_client.balance(&owner), 5_000);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+        assert_eq!(client.get_lock(&owner, &plain).status, LockStatus::Expired);
+        assert_eq!(client.get_lock(&owner, &to_payee).status, LockStatus::Expired);
+        assert_eq!(client.solvency(&token_addr), (0, 0));
+    }
 
-        // Withdraw 1000
-        client.withdraw(&owner, &token_addr, &1_000);
-        assert_eq!(client.balance(&owner, &token_addr), 4_000);
-        assert_eq!(token_client.balance(&owner), 6_000);
+    #[test]
+    fn test_recovery_reclaim() {
+        let env = Env::default();
+        env.mock_all_auths();
 
-        // Lock 2000, expires at ledger 1000
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recovery = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+        let lock_id = client.lock(&owner, &token_addr, &1_000, &100);
+        client.set_recovery(&owner, &recovery);
+        assert_eq!(client.recovery(&owner), Some(recovery.clone()));
+
+        env.ledger().set_sequence_number(101);
+        assert_eq!(
+            client.try_reclaim_as(&stranger, &owner, &lock_id),
+            Err(Ok(VaultError::NotRecovery))
+        );
+
+        client.reclaim_as(&recovery, &owner, &lock_id);
+        assert_eq!(env.auths()[0].0, recovery);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Expired);
+        assert_eq!(client.balance(&owner, &token_addr), 1_000);
+        assert_eq!(client.balance(&recovery, &token_addr), 0);
+    }
+
+    #[test]
+    fn test_vesting_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+        let lock_id = client.lock_vesting(&owner, &token_addr, &1_000, &100, &300, &payee);
+
+        // 0%: nothing before or at the start
         env.ledger().set_sequence_number(100);
-        let lock_id = client.lock(&owner, &token_addr, &2_000, &1_000);
-        assert_eq!(lock_id, 0);
-        assert_eq!(client.balance(&owner, &token_addr), 2_000);
+        assert_eq!(client.unlocked_amount(&owner, &lock_id), 0);
+        assert_eq!(
+            client.try_release_vested(&owner, &lock_id),
+            Err(Ok(VaultError::InvalidAmount))
+        );
 
-        // Verify lock entry
-        let entry = client.get_lock(&owner, &lock_id);
-        assert_eq!(entry.amount, 2_000);
-        assert_eq!(entry.status, LockStatus::Active);
+        // 50%
+        env.ledger().set_sequence_number(200);
+        assert_eq!(client.unlocked_amount(&owner, &lock_id), 500);
+        assert_eq!(client.release_vested(&owner, &lock_id), 500);
+        assert_eq!(env.auths()[0].0, payee);
+        assert_eq!(token_client.balance(&payee), 500);
+        assert_eq!(client.unlocked_amount(&owner, &lock_id), 0);
+        assert_eq!(client.get_lock(&owner, &lock_id).amount, 500);
+
+        // 100%, and beyond the end nothing more vests
+        env.ledger().set_sequence_number(400);
+        assert_eq!(client.unlocked_amount(&owner, &lock_id), 500);
+        assert_eq!(client.release_vested(&owner, &lock_id), 500);
+        assert_eq!(token_client.balance(&payee), 1_000);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Released);
+        assert_eq!(client.unlocked_amount(&owner, &lock_id), 0);
+
+        // Unknown locks read as nothing unlocked
+        assert_eq!(client.unlocked_amount(&owner, &99), 0);
+    }
+
+    /// Prices tokens from a table set with `set_price`.
+    #[contract]
+    struct MockOracle;
+
+    #[contractimpl]
+    impl MockOracle {
+        pub fn set_price(env: Env, token: Address, price: i128) {
+            env.storage().instance().set(&token, &price);
+        }
+
+        pub fn price(env: Env, token: Address) -> Option<i128> {
+            env.storage().instance().get(&token)
+        }
+    }
+
+    #[test]
+    fn test_portfolio_value() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+        let oracle_id = env.register_contract(None, MockOracle);
+        let oracle = MockOracleClient::new(&env, &oracle_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (usdc, _, usdc_admin) = setup_token(&env, &admin);
+        let (xlm, _, xlm_admin) = setup_token(&env, &admin);
+        let (unpriced, _, unpriced_admin) = setup_token(&env, &admin);
+        usdc_admin.mint(&owner, &1_000);
+        xlm_admin.mint(&owner, &4_000);
+        unpriced_admin.mint(&owner, &500);
+
+        oracle.set_price(&usdc, &PRICE_SCALE);
+        oracle.set_price(&xlm, &(PRICE_SCALE / 4));
+
+        client.init(&owner);
+        assert_eq!(client.portfolio_value(&owner, &oracle_id), 0);
+
+        client.deposit(&owner, &usdc, &1_000);
+        client.deposit(&owner, &xlm, &4_000);
+        client.deposit(&owner, &unpriced, &500);
+
+        // 1000 * 1 + 4000 * 0.25; the unpriced token is skipped
+        assert_eq!(client.portfolio_value(&owner, &oracle_id), 2_000);
+
+        // A price whose value overflows skips that token instead of
+        // panicking the whole view
+        oracle.set_price(&unpriced, &i128::MAX);
+        assert_eq!(client.portfolio_value(&owner, &oracle_id), 2_000);
+    }
+
+    #[test]
+    fn test_reserve_and_fund_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+
+        // Reserved before any funds are deposited
+        let lock_id = client.reserve_lock(&owner, &token_addr, &1_000, &100);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Pending);
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &recipient),
+            Err(Ok(VaultError::LockNotActive))
+        );
+        assert_eq!(
+            client.try_fund_lock(&owner, &lock_id),
+            Err(Ok(VaultError::InsufficientFunds))
+        );
+
+        client.deposit(&owner, &token_addr, &1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 1_000);
+
+        client.fund_lock(&owner, &lock_id);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
+        assert!(client.check_invariants(&owner, &token_addr));
+        assert_eq!(
+            client.try_cancel_reservation(&owner, &lock_id),
+            Err(Ok(VaultError::LockNotActive))
+        );
 
-        // Release to recipient
         client.release(&owner, &lock_id, &recipient);
-        assert_eq!(token_client.balance(&recipient), 2_000);
+        assert_eq!(token_client.balance(&recipient), 1_000);
+    }
 
-        let entry = client.get_lock(&owner, &lock_id);
-        assert_eq!(entry.status, LockStatus::Released);
+    #[test]
+    fn test_cancel_reservation() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, _) = setup_token(&env, &admin);
+
+        client.init(&owner);
+        let lock_id = client.reserve_lock(&owner, &token_addr, &1_000, &100);
+        client.cancel_reservation(&owner, &lock_id);
+
+        assert!(matches!(
+            client.try_get_lock(&owner, &lock_id),
+            Err(Ok(VaultError::LockNotFound))
+        ));
+        assert_eq!(
+            client.try_fund_lock(&owner, &lock_id),
+            Err(Ok(VaultError::LockNotFound))
+        );
+        // The id stays used
+        assert_eq!(client.reserve_lock(&owner, &token_addr, &1_000, &100), lock_id + 1);
     }
 
     #[test]
-    fn test_reclaim_expired() {
+    fn test_vesting_lock_cannot_split() {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(EscrowVault, ());
+        let contract_id = env.register_contract(None, EscrowVault);
         let client = EscrowVaultClient::new(&env, &contract_id);
 
         let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
         let admin = Address::generate(&env);
-        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
-        token_admin.mint(&owner, &5_000);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &2_000);
 
         client.init(&owner);
-        client.deposit(&owner, &token_addr, &3_000);
+        client.deposit(&owner, &token_addr, &2_000);
+        let lock_id = client.lock_vesting(&owner, &token_addr, &1_000, &100, &300, &payee);
+
+        assert_eq!(
+            client.try_split_lock(&owner, &lock_id, &400),
+            Err(Ok(VaultError::VestingLock))
+        );
+        assert_eq!(
+            client.try_transfer_lock(&owner, &lock_id, &payee),
+            Err(Ok(VaultError::VestingLock))
+        );
+        assert_eq!(
+            client.try_deposit_and_extend(&owner, &token_addr, &500, &lock_id, &500),
+            Err(Ok(VaultError::VestingLock))
+        );
+
+        // Only the lock's own funds are ever paid
+        env.ledger().set_sequence_number(300);
+        assert_eq!(client.release_vested(&owner, &lock_id), 1_000);
+        assert_eq!(token_client.balance(&payee), 1_000);
+        assert_eq!(client.balance(&owner, &token_addr), 1_000);
+        assert!(client.check_invariants(&owner, &token_addr));
+    }
+
+    #[test]
+    fn test_vesting_lock_not_reclaimable() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let payee = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &1_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &1_000);
+        let lock_id = client.lock_vesting(&owner, &token_addr, &1_000, &100, &300, &payee);
+
+        env.ledger().set_sequence_number(200);
+        assert_eq!(
+            client.try_release(&owner, &lock_id, &owner),
+            Err(Ok(VaultError::VestingLock))
+        );
+
+        // Fully vested but unclaimed: the owner can't pull it back
+        env.ledger().set_sequence_number(301);
+        assert_eq!(
+            client.try_reclaim(&owner, &lock_id),
+            Err(Ok(VaultError::VestingLock))
+        );
+        let ids = Vec::from_array(&env, [lock_id]);
+        assert_eq!(client.reclaim_batch(&owner, &ids), 0);
+
+        client.set_emergency_at(&1);
+        assert_eq!(client.emergency_withdraw(&owner, &token_addr), 0);
+        assert_eq!(client.get_lock(&owner, &lock_id).status, LockStatus::Active);
+
+        assert_eq!(client.release_vested(&owner, &lock_id), 1_000);
+        assert_eq!(token_client.balance(&payee), 1_000);
+    }
+
+    #[test]
+    fn test_interest_on_other_release_routes() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|li| {
+            li.min_persistent_entry_ttl = 100_000;
+            li.min_temp_entry_ttl = 100_000;
+            li.max_entry_ttl = 1_000_000;
+        });
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let sponsor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &300_000);
+        token_admin.mint(&sponsor, &10_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &300_000);
+        client.fund_reserve(&sponsor, &token_addr, &10_000);
+        client.set_max_interest_rate(&token_addr, &50);
 
-        // Lock expires at ledger 200
         env.ledger().set_sequence_number(100);
-        let lock_id = client.lock(&owner, &token_addr, &2_000, &200);
+        // 50 bps per 1000 ledgers: 2_000 interest on 100_000 after 4_000 ledgers
+        let offered = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+        let settled = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+        let disputed = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+        client.dispute_lock(&owner, &disputed);
 
-        // Advance past expiry
-        env.ledger().set_sequence_number(201);
+        env.ledger().set_sequence_number(4_100);
+        client.offer_release(&owner, &offered, &recipient);
+        client.accept_release(&recipient, &owner, &offered);
+        assert_eq!(token_client.balance(&recipient), 102_000);
 
-        // Reclaim expired funds
-        client.reclaim(&owner, &lock_id);
-        assert_eq!(client.balance(&owner, &token_addr), 3_000); // 1000 remaining + 2000 reclaimed
+        // Expired without a beneficiary: settle returns it to the owner,
+        // without interest
+        env.ledger().set_sequence_number(4_101);
+        client.settle(&keeper, &owner, &settled);
+        assert_eq!(client.balance(&owner, &token_addr), 100_000);
 
-        let entry = client.get_lock(&owner, &lock_id);
-        assert_eq!(entry.status, LockStatus::Expired);
+        client.resolve_dispute(&owner, &disputed, &Some(recipient.clone()));
+        assert_eq!(token_client.balance(&recipient), 204_000);
+
+        assert_eq!(client.reserve(&token_addr), 6_000);
+        let (held, accounted) = client.solvency(&token_addr);
+        assert_eq!(held, accounted);
     }
 
     #[test]
-    #[should_panic(expected = "Error(Contract, #4)")]
-    fn test_withdraw_insufficient() {
+    fn test_owner_gets_no_interest() {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(EscrowVault, ());
+        let contract_id = env.register_contract(None, EscrowVault);
         let client = EscrowVaultClient::new(&env, &contract_id);
 
         let owner = Address::generate(&env);
+        let sponsor = Address::generate(&env);
         let admin = Address::generate(&env);
-        let (token_addr, _token_client, token_admin) = setup_token(&env, &admin);
-        token_admin.mint(&owner, &100);
+        let (token_addr, token_client, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &200_000);
+        token_admin.mint(&sponsor, &10_000);
 
         client.init(&owner);
-        client.deposit(&owner, &token_addr, &100);
-        client.withdraw(&owner, &token_addr, &200); // panics: InsufficientFunds
+        client.deposit(&owner, &token_addr, &200_000);
+        client.fund_reserve(&sponsor, &token_addr, &10_000);
+        client.set_max_interest_rate(&token_addr, &50);
+
+        env.ledger().set_sequence_number(100);
+        let reclaimed = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+        let to_self = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+
+        // Releasing to yourself doesn't pay interest
+        env.ledger().set_sequence_number(4_100);
+        assert_eq!(client.accrued_interest(&owner, &to_self), 2_000);
+        client.release(&owner, &to_self, &owner);
+        assert_eq!(token_client.balance(&owner), 100_000);
+
+        // Nor does reclaiming an expired lock: only the principal comes back
+        env.ledger().set_sequence_number(4_101);
+        assert_eq!(client.accrued_interest(&owner, &reclaimed), 2_000);
+        client.reclaim(&owner, &reclaimed);
+        assert_eq!(client.balance(&owner, &token_addr), 100_000);
+
+        assert_eq!(client.reserve(&token_addr), 10_000);
+        let (held, accounted) = client.solvency(&token_addr);
+        assert_eq!(held, accounted);
+    }
+
+    #[test]
+    fn test_interest_lock_cannot_top_up() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, EscrowVault);
+        let client = EscrowVaultClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let admin = Address::generate(&env);
+        let (token_addr, _, token_admin) = setup_token(&env, &admin);
+        token_admin.mint(&owner, &200_000);
+
+        client.init(&owner);
+        client.deposit(&owner, &token_addr, &100_000);
+        client.set_max_interest_rate(&token_addr, &50);
+
+        env.ledger().set_sequence_number(100);
+        let lock_id = client.lock_with_interest(&owner, &token_addr, &100_000, &4_100, &50);
+
+        // A top-up just before expiry would otherwise earn interest for
+        // the whole life of the lock
+        env.ledger().set_sequence_number(4_000);
+        assert_eq!(
+            client.try_deposit_and_extend(&owner, &token_addr, &100_000, &lock_id, &100_000),
+            Err(Ok(VaultError::InterestLock))
+        );
+        assert_eq!(client.get_lock(&owner, &lock_id).amount, 100_000);
+        assert_eq!(client.balance(&owner, &token_addr), 0);
     }
 }
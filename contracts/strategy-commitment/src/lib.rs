@@ -1,9 +1,12 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Bytes, BytesN, Env,
+    contract, contractclient, contracterror, contractimpl, contracttype, panic_with_error,
+    symbol_short, token, xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Symbol, Topics, Val, Vec,
 };
 
+mod poseidon;
+
 // ─── Storage keys ────────────────────────────────────────────────────────────
 
 #[contracttype]
@@ -14,6 +17,106 @@ pub enum DataKey {
     NextProofId,
     Proof(u64),
     ProofByCommit(u64),
+    CommitProofs(u64),
+    // Typed strategies revealed via reveal_spec
+    Spec(u64),
+    // Per-owner id indexes for listing
+    OwnerCommits(Address),
+    OwnerProofs(Address),
+    // Proof ids by the trade tx hash they were attached with
+    ProofByTx(Bytes),
+    // Most recent live commitment per owner
+    LatestCommit(Address),
+    // Commit ids by day (timestamp / 86400), for analytics
+    CommitsByDay(u64),
+    // Public half of the salt revealed via reveal_two_salt
+    PublicSalt(u64),
+    // Extra addresses allowed to reveal a commitment via reveal_as
+    RevealAcl(u64),
+    // Compressed-store commitments: sha256 of the compressed blob (set at
+    // commit) and sha256 of the raw strategy (set at reveal)
+    CompressedHash(u64),
+    StrategyHash(u64),
+    // Keeper reveal intent set via arm_reveal
+    RevealIntent(u64),
+    // Commit ids by commitment hash, for find_by_hash
+    CommitByHash(BytesN<32>),
+    // Commitments announced in advance via register_expectation:
+    // (registrant, expected owner, commitment) → true
+    Expectation(Address, Address, BytesN<32>),
+    // Registrants with an open expectation on (owner, commitment)
+    Expecters(Address, BytesN<32>),
+    // Configuration
+    Admin,
+    EventLevel,
+    MinSaltLen,
+    StrictProofs,
+    RevealFee,
+}
+
+// ─── Event verbosity ─────────────────────────────────────────────────────────
+
+/// Emit nothing.
+pub const EVENTS_NONE: u32 = 0;
+/// Emit only commits and reveals.
+pub const EVENTS_KEY: u32 = 1;
+/// Emit everything, including proof attachments and proof reveals.
+pub const EVENTS_VERBOSE: u32 = 2;
+
+// ─── Paging ──────────────────────────────────────────────────────────────────
+
+/// Maximum number of entries any `list_*` call returns.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Maximum number of index entries a whole-owner scan
+/// (`find_matching_commitment`) inspects.
+pub const MAX_SCAN: u32 = 200;
+
+/// Maximum total strategy bytes `revealed_strategies` returns per call.
+pub const MAX_PAGE_BYTES: u32 = 16_384;
+
+/// Maximum number of tags on a commitment.
+pub const MAX_TAGS: u32 = 8;
+
+/// Maximum number of links `commitment_chain` follows.
+pub const MAX_CHAIN_DEPTH: u32 = 32;
+
+/// Maximum ids recorded per `commits_on_day` bucket; later commits that day
+/// are not bucketed.
+pub const MAX_DAY_BUCKET: u32 = 500;
+
+/// Maximum number of addresses in a commitment's reveal ACL.
+pub const MAX_REVEAL_ACL: u32 = 8;
+
+/// Maximum open expectations on one (owner, commitment) pair, bounding the
+/// work a matching commit does to clear them.
+pub const MAX_EXPECTATIONS: u32 = 8;
+
+// ─── Fee estimation ──────────────────────────────────────────────────────────
+
+/// XDR size of a `CommitmentRecord` with an empty strategy plus its storage
+/// key, in bytes. Used by `estimate_reveal_footprint`.
+pub const REVEAL_FOOTPRINT_OVERHEAD: u32 = 584;
+
+// ─── Hash algorithms ─────────────────────────────────────────────────────────
+
+/// Hash used to build a commitment from `strategy || salt`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashAlg {
+    Sha256,
+    /// SNARK-friendly; see `poseidon.rs` for the parameter set.
+    Poseidon,
+}
+
+/// How the salt is bound to the strategy.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitScheme {
+    /// `hash_alg(strategy || salt)`.
+    Concat,
+    /// HMAC-SHA256 keyed by the salt: `HMAC(salt, strategy)`.
+    Hmac,
 }
 
 // ─── Stored commitment record ────────────────────────────────────────────────
@@ -26,6 +129,68 @@ pub struct CommitmentRecord {
     pub revealed: bool,
     pub strategy: Bytes,
     pub timestamp: u64,
+    /// Optional reveal deadline (ledger timestamp). Past it, an unrevealed
+    /// commitment can no longer be revealed and may be purged by anyone.
+    pub expires_at: Option<u64>,
+    pub hash_alg: HashAlg,
+    /// Merkle root of proof hashes committed up front; members are revealed
+    /// with `reveal_proof_in_bundle`. Held as 32 raw bytes because SDK 21
+    /// cannot nest `BytesN` in an `Option` field.
+    pub proof_root: Option<Bytes>,
+    pub scheme: CommitScheme,
+    /// Compliance hold set by the admin; blocks reveal, cancel, rotation
+    /// and proof attachment until cleared.
+    pub frozen: bool,
+    /// Earlier commitment this one evolves from, set via `commit_with_parent`.
+    pub parent_id: Option<u64>,
+    /// Reveal window bounds (ledger timestamps, inclusive) set via
+    /// `commit_with_window`. Unlike `expires_at`, leaving the window does
+    /// not make the commitment purgeable.
+    pub valid_from: Option<u64>,
+    pub valid_to: Option<u64>,
+    /// Optional keccak256(strategy || salt) set via `commit_dual`; when
+    /// present, reveals must match it as well as `commitment`. Held as raw
+    /// bytes for the same reason as `proof_root`.
+    pub keccak_commitment: Option<Bytes>,
+    /// Set by `reveal_compressed`: `strategy` holds the client-compressed
+    /// form, not the raw preimage. See `commit_compressed`.
+    pub compressed: bool,
+    /// Owner-chosen labels, at most `MAX_TAGS`, for `list_by_tags`.
+    pub tags: Vec<Symbol>,
+    /// Contract whose `verify` must approve the strategy at reveal, set via
+    /// `commit_with_verifier`.
+    pub verifier: Option<Address>,
+}
+
+/// Returned by `reveal` so callers can confirm it without a follow-up `get`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevealReceipt {
+    pub commit_id: u64,
+    /// Ledger timestamp of the reveal.
+    pub revealed_at: u64,
+    pub strategy_len: u32,
+}
+
+/// Fee charged on every reveal when configured via `set_reveal_fee`:
+/// `amount` of `token`, paid by the revealer to `collector`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevealFee {
+    pub token: Address,
+    pub amount: i128,
+    pub collector: Address,
+}
+
+// ─── Structured strategy ─────────────────────────────────────────────────────
+
+/// A typed strategy. Committed as sha256(spec.to_xdr() || salt).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategySpec {
+    pub asset: Symbol,
+    pub side: Symbol,
+    pub threshold: i128,
 }
 
 // ─── Proof attachment record ─────────────────────────────────────────────────
@@ -41,11 +206,13 @@ pub struct ProofRecord {
     pub strategy: Bytes,
     pub trade_params: Bytes,
     pub timestamp: u64,
+    /// Realized PnL recorded by the owner after reveal via `record_pnl`.
+    pub pnl: Option<i128>,
 }
 
 // ─── Errors ──────────────────────────────────────────────────────────────────
 
-#[contracttype]
+#[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum Error {
@@ -57,6 +224,48 @@ pub enum Error {
     ProofNotFound = 6,
     ProofAlreadyRevealed = 7,
     ProofHashMismatch = 8,
+    WeakSalt = 9,
+    NoProofRoot = 10,
+    NotInBundle = 11,
+    BadContinuityProof = 12,
+    Frozen = 13,
+    NotYetValid = 14,
+    Expired = 15,
+    ProofNotRevealed = 16,
+    PendingProofs = 17,
+    NotRevealer = 18,
+    AclTooLarge = 19,
+    NotCompressed = 20,
+    TooManyTags = 21,
+    NotArmed = 22,
+    IntentMismatch = 23,
+    VerifierRejected = 24,
+    TooManyExpectations = 25,
+    DualCommitment = 26,
+}
+
+// ─── External verifier ───────────────────────────────────────────────────────
+
+/// Validation hook named by `commit_with_verifier`. Called after the hash
+/// check on every reveal of that commitment; returning false reverts it.
+/// Commitment reveals carry no trade parameters, so `trade_params` is empty.
+#[contractclient(name = "VerifierClient")]
+pub trait Verifier {
+    fn verify(env: Env, strategy: Bytes, trade_params: Bytes) -> bool;
+}
+
+// ─── Reveal authorization ────────────────────────────────────────────────────
+
+/// Who must authorize a reveal in `open_commit`.
+enum RevealAuth<'a> {
+    /// The commitment owner.
+    Owner,
+    /// The given address, which must be the owner or in the reveal ACL.
+    Delegate(&'a Address),
+    /// The admin, already authorized by the caller.
+    Admin,
+    /// A keeper bound by the owner's armed reveal intent.
+    Keeper(&'a Address),
 }
 
 // ─── Contract ────────────────────────────────────────────────────────────────
@@ -66,254 +275,3238 @@ pub struct StrategyCommitment;
 
 #[contractimpl]
 impl StrategyCommitment {
-    /// Commit a strategy hash on-chain. Returns the commit_id.
-    ///
-    /// `commitment` = SHA-256(strategy_bytes || salt_bytes), computed off-chain.
-    pub fn commit(env: Env, owner: Address, commitment: BytesN<32>) -> u64 {
-        owner.require_auth();
+    /// Set the contract admin. Can only be called once.
+    pub fn init(env: Env, admin: Address) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("already initialized");
+        }
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
 
-        // Auto-increment ID
-        let id: u64 = env
-            .storage()
-            .instance()
-            .get(&DataKey::NextId)
-            .unwrap_or(0);
+    /// Set the event verbosity (`EVENTS_NONE`, `EVENTS_KEY` or
+    /// `EVENTS_VERBOSE`). Admin only.
+    pub fn set_event_level(env: Env, level: u32) {
+        Self::require_admin(&env);
+        if level > EVENTS_VERBOSE {
+            panic!("invalid event level");
+        }
+        env.storage().instance().set(&DataKey::EventLevel, &level);
+    }
 
-        let record = CommitmentRecord {
-            owner: owner.clone(),
-            commitment,
-            revealed: false,
-            strategy: Bytes::new(&env),
-            timestamp: env.ledger().timestamp(),
-        };
+    /// In strict mode a commitment can't be revealed while any proof
+    /// attached to it is still unrevealed. Off by default. Admin only.
+    pub fn set_strict_proofs(env: Env, enabled: bool) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::StrictProofs, &enabled);
+    }
 
+    /// Whether strict proof ordering is enabled.
+    pub fn strict_proofs(env: Env) -> bool {
         env.storage()
-            .persistent()
-            .set(&DataKey::Commitment(id), &record);
+            .instance()
+            .get(&DataKey::StrictProofs)
+            .unwrap_or(false)
+    }
+
+    /// Require reveal salts to be at least `len` bytes. 0 disables the
+    /// policy. Admin only.
+    pub fn set_min_salt_len(env: Env, len: u32) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::MinSaltLen, &len);
+    }
 
+    /// Minimum reveal salt length in bytes. Defaults to 0 (no minimum).
+    pub fn min_salt_len(env: Env) -> u32 {
         env.storage()
             .instance()
-            .set(&DataKey::NextId, &(id + 1));
+            .get(&DataKey::MinSaltLen)
+            .unwrap_or(0)
+    }
 
-        // Emit event
-        env.events()
-            .publish((symbol_short!("commit"),), (id, owner));
+    /// Charge `amount` of `token`, sent to `collector`, on every reveal.
+    /// The revealer (owner, ACL delegate or keeper) pays; admin reveals are
+    /// free. An `amount` of 0 or less disables the fee. Admin only.
+    pub fn set_reveal_fee(env: Env, token: Address, amount: i128, collector: Address) {
+        Self::require_admin(&env);
+        if amount <= 0 {
+            env.storage().instance().remove(&DataKey::RevealFee);
+        } else {
+            let fee = RevealFee { token, amount, collector };
+            env.storage().instance().set(&DataKey::RevealFee, &fee);
+        }
+    }
 
-        id
+    /// The configured reveal fee, if any. Disabled by default.
+    pub fn reveal_fee(env: Env) -> Option<RevealFee> {
+        env.storage().instance().get(&DataKey::RevealFee)
     }
 
-    /// Read a commitment record by ID.
-    pub fn get(env: Env, commit_id: u64) -> CommitmentRecord {
+    /// Current event verbosity. Defaults to `EVENTS_VERBOSE`.
+    pub fn event_level(env: Env) -> u32 {
         env.storage()
-            .persistent()
-            .get(&DataKey::Commitment(commit_id))
-            .unwrap_or_else(|| panic!("commitment not found"))
+            .instance()
+            .get(&DataKey::EventLevel)
+            .unwrap_or(EVENTS_VERBOSE)
     }
 
-    /// Reveal: prove that hash(strategy || salt) == commitment.
+    /// Commit a strategy hash on-chain. Returns the commit_id.
     ///
-    /// On success, stores the plaintext strategy in the record and marks revealed.
-    pub fn reveal(env: Env, commit_id: u64, strategy: Bytes, salt: Bytes) {
-        let mut record: CommitmentRecord = env
+    /// `commitment` = SHA-256(strategy_bytes || salt_bytes), computed off-chain.
+    pub fn commit(env: Env, owner: Address, commitment: BytesN<32>) -> u64 {
+        owner.require_auth();
+        let record = Self::new_record(&env, owner, commitment);
+        Self::store_commit(&env, record)
+    }
+
+    /// Commit with a reveal deadline (ledger timestamp). Returns the commit_id.
+    ///
+    /// The commitment must be revealed by `expires_at`; afterwards it can be
+    /// purged via `purge_expired_commitment`.
+    pub fn commit_with_deadline(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        expires_at: u64,
+    ) -> u64 {
+        owner.require_auth();
+
+        if expires_at <= env.ledger().timestamp() {
+            panic!("invalid deadline");
+        }
+
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.expires_at = Some(expires_at);
+        Self::store_commit(&env, record)
+    }
+
+    /// As `registrant`, announce that `who` is expected to commit
+    /// `commitment`. When a matching commit from `who` lands, an `exp_met`
+    /// event is emitted for each registrant and their expectations are
+    /// cleared. Requires `registrant`'s auth; at most `MAX_EXPECTATIONS`
+    /// registrants per (who, commitment).
+    pub fn register_expectation(
+        env: Env,
+        registrant: Address,
+        who: Address,
+        commitment: BytesN<32>,
+    ) {
+        registrant.require_auth();
+
+        let key = DataKey::Expectation(registrant.clone(), who.clone(), commitment.clone());
+        if !env.storage().persistent().has(&key) {
+            let index_key = DataKey::Expecters(who.clone(), commitment.clone());
+            let mut expecters: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&index_key)
+                .unwrap_or_else(|| Vec::new(&env));
+            if expecters.len() >= MAX_EXPECTATIONS {
+                panic_with_error!(&env, Error::TooManyExpectations);
+            }
+            expecters.push_back(registrant.clone());
+            env.storage().persistent().set(&index_key, &expecters);
+            env.storage().persistent().extend_ttl(&index_key, 518_400, 518_400);
+        }
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (symbol_short!("expect"),),
+            (registrant, who, commitment),
+        );
+    }
+
+    /// Withdraw an unmet expectation `registrant` registered on `who`.
+    /// Requires `registrant`'s auth.
+    pub fn cancel_expectation(
+        env: Env,
+        registrant: Address,
+        who: Address,
+        commitment: BytesN<32>,
+    ) {
+        registrant.require_auth();
+
+        let key = DataKey::Expectation(registrant.clone(), who.clone(), commitment.clone());
+        if !env.storage().persistent().has(&key) {
+            panic_with_error!(&env, Error::NotFound);
+        }
+        env.storage().persistent().remove(&key);
+
+        let index_key = DataKey::Expecters(who.clone(), commitment.clone());
+        let mut expecters: Vec<Address> = env
             .storage()
             .persistent()
-            .get(&DataKey::Commitment(commit_id))
-            .unwrap_or_else(|| panic!("commitment not found"));
+            .get(&index_key)
+            .unwrap_or_else(|| Vec::new(&env));
+        if let Some(i) = expecters.first_index_of(&registrant) {
+            expecters.remove(i);
+        }
+        if expecters.is_empty() {
+            env.storage().persistent().remove(&index_key);
+        } else {
+            env.storage().persistent().set(&index_key, &expecters);
+        }
 
-        // Only the owner can reveal
-        record.owner.require_auth();
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (symbol_short!("exp_cncl"),),
+            (registrant, who, commitment),
+        );
+    }
 
-        if record.revealed {
-            panic!("already revealed");
+    /// Whether `registrant` still has an unmet expectation that `who` will
+    /// commit `commitment`.
+    pub fn is_expected(
+        env: Env,
+        registrant: Address,
+        who: Address,
+        commitment: BytesN<32>,
+    ) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Expectation(registrant, who, commitment))
+    }
+
+    /// Commit with an external `verifier` that must approve the strategy
+    /// when it is revealed. Returns the commit_id.
+    pub fn commit_with_verifier(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        verifier: Address,
+    ) -> u64 {
+        owner.require_auth();
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.verifier = Some(verifier);
+        Self::store_commit(&env, record)
+    }
+
+    /// Commit with up to `MAX_TAGS` labels for `list_by_tags`. Returns the
+    /// commit_id.
+    pub fn commit_with_tags(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        tags: Vec<Symbol>,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+        if tags.len() > MAX_TAGS {
+            return Err(Error::TooManyTags);
         }
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.tags = tags;
+        Ok(Self::store_commit(&env, record))
+    }
 
-        // Reconstruct: hash(strategy || salt)
-        let mut preimage = Bytes::new(&env);
-        preimage.append(&strategy);
-        preimage.append(&salt);
+    /// Replace a commitment's tags. Owner auth.
+    pub fn set_tags(env: Env, commit_id: u64, tags: Vec<Symbol>) -> Result<(), Error> {
+        let key = DataKey::Commitment(commit_id);
+        let mut record: CommitmentRecord =
+            env.storage().persistent().get(&key).ok_or(Error::NotFound)?;
 
-        let computed: BytesN<32> = env.crypto().sha256(&preimage).into();
+        record.owner.require_auth();
 
-        if computed != record.commitment {
-            panic!("hash mismatch");
+        if tags.len() > MAX_TAGS {
+            return Err(Error::TooManyTags);
         }
+        record.tags = tags;
+        env.storage().persistent().set(&key, &record);
 
-        record.revealed = true;
-        record.strategy = strategy;
+        Self::emit(&env, EVENTS_VERBOSE, (symbol_short!("tags"),), commit_id);
+        Ok(())
+    }
 
+    /// Commit for a compressed-store reveal. `commitment` is the usual
+    /// sha256(strategy || salt) over the raw strategy; `compressed_hash` is
+    /// sha256 of the compressed blob the owner will store at reveal.
+    ///
+    /// `reveal_compressed` checks both hashes, stores the blob in place of
+    /// the strategy and records sha256(strategy) from the verified
+    /// preimage as `strategy_hash`. Readers decompress the stored blob and
+    /// accept it only if its sha256 equals `strategy_hash`; the contract
+    /// never decompresses, so the codec is up to the client. Returns the
+    /// commit_id.
+    pub fn commit_compressed(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        compressed_hash: BytesN<32>,
+    ) -> u64 {
+        owner.require_auth();
+        let record = Self::new_record(&env, owner, commitment);
+        let commit_id = Self::store_commit(&env, record);
         env.storage()
             .persistent()
-            .set(&DataKey::Commitment(commit_id), &record);
+            .set(&DataKey::CompressedHash(commit_id), &compressed_hash);
+        commit_id
+    }
 
-        // Emit event
-        env.events()
-            .publish((symbol_short!("reveal"),), (commit_id, record.owner));
+    /// Commit under both SHA-256 and Keccak-256 of `strategy || salt`, so a
+    /// break in either hash alone can't forge a reveal. Returns the
+    /// commit_id.
+    pub fn commit_dual(
+        env: Env,
+        owner: Address,
+        commitment_sha: BytesN<32>,
+        commitment_keccak: BytesN<32>,
+    ) -> u64 {
+        owner.require_auth();
+        let mut record = Self::new_record(&env, owner, commitment_sha);
+        record.keccak_commitment = Some(commitment_keccak.into());
+        Self::store_commit(&env, record)
     }
 
-    // ─── Proof Attachments ──────────────────────────────────────────────
+    /// Commit with a reveal window: `reveal` is only accepted while the
+    /// ledger timestamp is within `[valid_from, valid_to]`. Returns the
+    /// commit_id.
+    pub fn commit_with_window(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        valid_from: u64,
+        valid_to: u64,
+    ) -> u64 {
+        owner.require_auth();
 
-    /// Attach a proof hash on-chain, linked to an existing commitment and a trade tx.
+        if valid_from > valid_to || valid_to < env.ledger().timestamp() {
+            panic!("invalid window");
+        }
+
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.valid_from = Some(valid_from);
+        record.valid_to = Some(valid_to);
+        Self::store_commit(&env, record)
+    }
+
+    /// Commit using a specific hash algorithm. Returns the commit_id.
     ///
-    /// `proof_hash` = SHA-256(strategy || trade_params || salt), computed off-chain.
-    /// Returns the proof_id.
-    pub fn attach_proof(
+    /// `commitment` = alg(strategy_bytes || salt_bytes), computed off-chain.
+    pub fn commit_with_alg(env: Env, owner: Address, commitment: BytesN<32>, alg: HashAlg) -> u64 {
+        owner.require_auth();
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.hash_alg = alg;
+        Self::store_commit(&env, record)
+    }
+
+    /// Commit using a specific salt-binding scheme. Returns the commit_id.
+    ///
+    /// For `CommitScheme::Hmac`, `commitment` = HMAC-SHA256(key = salt,
+    /// message = strategy), computed off-chain.
+    pub fn commit_with_scheme(
         env: Env,
         owner: Address,
-        proof_hash: BytesN<32>,
-        commit_id: u64,
-        tx_hash: Bytes,
+        commitment: BytesN<32>,
+        scheme: CommitScheme,
     ) -> u64 {
         owner.require_auth();
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.scheme = scheme;
+        Self::store_commit(&env, record)
+    }
 
-        // Validate the commitment exists and belongs to the caller
-        let commit: CommitmentRecord = env
+    /// Commit a strategy that evolves from an existing commitment
+    /// `parent_id`. Returns the commit_id.
+    pub fn commit_with_parent(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        parent_id: u64,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+
+        if !env
             .storage()
             .persistent()
-            .get(&DataKey::Commitment(commit_id))
-            .unwrap_or_else(|| panic!("commitment not found"));
+            .has(&DataKey::Commitment(parent_id))
+        {
+            return Err(Error::NotFound);
+        }
 
-        if commit.owner != owner {
-            panic!("not owner");
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.parent_id = Some(parent_id);
+        Ok(Self::store_commit(&env, record))
+    }
+
+    /// Ids from `commit_id` back to the root of its parent chain, newest
+    /// first. Stops at a missing record or after `MAX_CHAIN_DEPTH` links.
+    pub fn commitment_chain(env: Env, commit_id: u64) -> Vec<u64> {
+        let mut chain = Vec::new(&env);
+        let mut next = Some(commit_id);
+        while let Some(id) = next {
+            if chain.len() >= MAX_CHAIN_DEPTH {
+                break;
+            }
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            match record {
+                Some(record) => {
+                    chain.push_back(id);
+                    next = record.parent_id;
+                }
+                None => break,
+            }
         }
+        chain
+    }
 
-        // Auto-increment proof ID
-        let proof_id: u64 = env
-            .storage()
-            .instance()
-            .get(&DataKey::NextProofId)
-            .unwrap_or(0);
+    /// Commit together with a Merkle root of proof hashes. Returns the
+    /// commit_id.
+    ///
+    /// Each leaf is SHA-256(0x00 || proof_hash); each parent is
+    /// SHA-256(0x01 || children in ascending byte order). The prefixes keep
+    /// an internal node from passing as a leaf.
+    pub fn commit_with_proof_root(
+        env: Env,
+        owner: Address,
+        commitment: BytesN<32>,
+        proof_root: BytesN<32>,
+    ) -> u64 {
+        owner.require_auth();
+        let mut record = Self::new_record(&env, owner, commitment);
+        record.proof_root = Some(proof_root.into());
+        Self::store_commit(&env, record)
+    }
 
-        let record = ProofRecord {
-            owner: owner.clone(),
-            proof_hash,
+    /// Read a commitment record by ID.
+    pub fn get(env: Env, commit_id: u64) -> CommitmentRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .unwrap_or_else(|| panic!("commitment not found"))
+    }
+
+    /// The stored record's canonical XDR encoding, for off-chain hashing and
+    /// archival.
+    pub fn commitment_xdr(env: Env, commit_id: u64) -> Bytes {
+        Self::get(env.clone(), commit_id).to_xdr(&env)
+    }
+
+    /// Reveal: prove that hash(strategy || salt) == commitment.
+    ///
+    /// On success, stores the plaintext strategy in the record, marks it
+    /// revealed and returns a receipt.
+    pub fn reveal(env: Env, commit_id: u64, strategy: Bytes, salt: Bytes) -> RevealReceipt {
+        let record = Self::open_commit(&env, RevealAuth::Owner, commit_id, &strategy, &salt);
+        let receipt = RevealReceipt {
             commit_id,
-            tx_hash,
-            revealed: false,
-            strategy: Bytes::new(&env),
-            trade_params: Bytes::new(&env),
-            timestamp: env.ledger().timestamp(),
+            revealed_at: env.ledger().timestamp(),
+            strategy_len: strategy.len(),
         };
+        Self::finish_reveal(&env, commit_id, record, strategy);
+        receipt
+    }
 
-        env.storage()
+    /// Reveal a `commit_compressed` commitment: verify the raw `strategy`
+    /// and `salt` as `reveal` does, check sha256(compressed) against the
+    /// committed hash, then store `compressed` as the record's strategy
+    /// with `compressed` set. Fails with `NotCompressed` for commitments
+    /// made without a compressed hash.
+    pub fn reveal_compressed(
+        env: Env,
+        commit_id: u64,
+        strategy: Bytes,
+        salt: Bytes,
+        compressed: Bytes,
+    ) {
+        let compressed_hash: BytesN<32> = env
+            .storage()
             .persistent()
-            .set(&DataKey::Proof(proof_id), &record);
+            .get(&DataKey::CompressedHash(commit_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotCompressed));
+        let mut record = Self::open_commit(&env, RevealAuth::Owner, commit_id, &strategy, &salt);
+
+        let computed: BytesN<32> = env.crypto().sha256(&compressed).into();
+        if computed != compressed_hash {
+            panic!("hash mismatch");
+        }
 
+        let strategy_hash: BytesN<32> = env.crypto().sha256(&strategy).into();
         env.storage()
             .persistent()
-            .set(&DataKey::ProofByCommit(commit_id), &proof_id);
+            .set(&DataKey::StrategyHash(commit_id), &strategy_hash);
 
-        env.storage()
-            .instance()
-            .set(&DataKey::NextProofId, &(proof_id + 1));
+        record.compressed = true;
+        Self::finish_reveal(&env, commit_id, record, compressed);
+    }
 
-        env.events()
-            .publish((symbol_short!("proof"),), (proof_id, owner, commit_id));
+    /// sha256 of the raw strategy of a commitment revealed via
+    /// `reveal_compressed`, for checking the decompressed blob.
+    pub fn strategy_hash(env: Env, commit_id: u64) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&DataKey::StrategyHash(commit_id))
+    }
 
-        proof_id
+    /// Reveal on the owner's behalf: like `reveal`, but authorized by
+    /// `revealer`, which must be the owner or in the commitment's reveal ACL.
+    pub fn reveal_as(env: Env, revealer: Address, commit_id: u64, strategy: Bytes, salt: Bytes) {
+        let auth = RevealAuth::Delegate(&revealer);
+        let record = Self::open_commit(&env, auth, commit_id, &strategy, &salt);
+        Self::finish_reveal(&env, commit_id, record, strategy);
     }
 
-    /// Read a proof record by ID.
-    pub fn get_proof(env: Env, proof_id: u64) -> ProofRecord {
-        env.storage()
-            .persistent()
-            .get(&DataKey::Proof(proof_id))
-            .unwrap_or_else(|| panic!("proof not found"))
+    /// Dispute escape hatch: record a reveal from a preimage supplied out of
+    /// band, without the owner's auth. The hash check and every other reveal
+    /// rule still apply, so the admin cannot fabricate a strategy. Emits an
+    /// `admin_rvl` event alongside the usual reveal event. Admin only.
+    pub fn admin_reveal(env: Env, commit_id: u64, strategy: Bytes, salt: Bytes) {
+        let admin = Self::require_admin(&env);
+        let record = Self::open_commit(&env, RevealAuth::Admin, commit_id, &strategy, &salt);
+        Self::finish_reveal(&env, commit_id, record, strategy);
+
+        Self::emit(&env, EVENTS_KEY, (symbol_short!("admin_rvl"),), (commit_id, admin));
     }
 
-    /// Reveal a proof: prove that hash(strategy || trade_params || salt) == proof_hash.
-    ///
-    /// On success, stores plaintext strategy and trade_params, marks revealed.
-    pub fn reveal_proof(
-        env: Env,
-        proof_id: u64,
-        strategy: Bytes,
-        trade_params: Bytes,
-        salt: Bytes,
-    ) {
-        let mut record: ProofRecord = env
+    /// Pre-authorize a keeper reveal without putting the salt on-chain:
+    /// `intent_hash` is sha256(strategy || salt || keeper.to_xdr()), binding
+    /// the preimage to the one keeper allowed to call `reveal_armed`.
+    /// Replaces any earlier intent. Owner auth.
+    pub fn arm_reveal(env: Env, commit_id: u64, intent_hash: BytesN<32>) -> Result<(), Error> {
+        let record: CommitmentRecord = env
             .storage()
             .persistent()
-            .get(&DataKey::Proof(proof_id))
-            .unwrap_or_else(|| panic!("proof not found"));
+            .get(&DataKey::Commitment(commit_id))
+            .ok_or(Error::NotFound)?;
 
         record.owner.require_auth();
 
         if record.revealed {
-            panic!("already revealed");
+            return Err(Error::AlreadyRevealed);
         }
 
-        // Reconstruct: hash(strategy || trade_params || salt)
-        let mut preimage = Bytes::new(&env);
-        preimage.append(&strategy);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RevealIntent(commit_id), &intent_hash);
+
+        Self::emit(&env, EVENTS_VERBOSE, (symbol_short!("armed"),), commit_id);
+        Ok(())
+    }
+
+    /// Reveal an armed commitment as `keeper`, without the owner's auth.
+    /// Besides the normal reveal checks, sha256(strategy || salt ||
+    /// keeper.to_xdr()) must equal the armed intent, so only the keeper the
+    /// owner named can use it.
+    pub fn reveal_armed(env: Env, keeper: Address, commit_id: u64, strategy: Bytes, salt: Bytes) {
+        let intent_key = DataKey::RevealIntent(commit_id);
+        let intent_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&intent_key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotArmed));
+
+        let mut preimage = Self::preimage(&env, &strategy, &salt);
+        preimage.append(&keeper.clone().to_xdr(&env));
+        let computed: BytesN<32> = env.crypto().sha256(&preimage).into();
+        if computed != intent_hash {
+            panic_with_error!(&env, Error::IntentMismatch);
+        }
+
+        let auth = RevealAuth::Keeper(&keeper);
+        let record = Self::open_commit(&env, auth, commit_id, &strategy, &salt);
+        env.storage().persistent().remove(&intent_key);
+        Self::finish_reveal(&env, commit_id, record, strategy);
+    }
+
+    /// Allow each address in `addrs` to reveal `commit_id` via `reveal_as`,
+    /// replacing any previous list. At most `MAX_REVEAL_ACL` entries; an
+    /// empty list clears the ACL. Owner auth.
+    pub fn set_reveal_acl(env: Env, commit_id: u64, addrs: Vec<Address>) -> Result<(), Error> {
+        let record: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .ok_or(Error::NotFound)?;
+
+        record.owner.require_auth();
+
+        if record.revealed {
+            return Err(Error::AlreadyRevealed);
+        }
+        if addrs.len() > MAX_REVEAL_ACL {
+            return Err(Error::AclTooLarge);
+        }
+
+        let key = DataKey::RevealAcl(commit_id);
+        if addrs.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &addrs);
+        }
+
+        Self::emit(&env, EVENTS_VERBOSE, (symbol_short!("acl"),), (commit_id, addrs.len()));
+        Ok(())
+    }
+
+    /// Addresses besides the owner allowed to reveal `commit_id`.
+    pub fn reveal_acl(env: Env, commit_id: u64) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RevealAcl(commit_id))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Reveal a structured strategy: prove that
+    /// hash(spec.to_xdr() || salt) == commitment.
+    ///
+    /// On success, stores the XDR bytes as the record's strategy and the
+    /// typed spec in a parallel `Spec` entry.
+    pub fn reveal_spec(env: Env, commit_id: u64, spec: StrategySpec, salt: Bytes) {
+        let strategy = spec.clone().to_xdr(&env);
+        let record = Self::open_commit(&env, RevealAuth::Owner, commit_id, &strategy, &salt);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Spec(commit_id), &spec);
+
+        Self::finish_reveal(&env, commit_id, record, strategy);
+    }
+
+    /// Reveal a commitment whose salt is a public nonce followed by a
+    /// private salt: prove that hash(strategy || public_salt || private_salt)
+    /// == commitment.
+    ///
+    /// On success, also stores `public_salt` so it can be read back with
+    /// `get_public_salt`. The private salt is never stored.
+    pub fn reveal_two_salt(
+        env: Env,
+        commit_id: u64,
+        strategy: Bytes,
+        public_salt: Bytes,
+        private_salt: Bytes,
+    ) {
+        let mut salt = public_salt.clone();
+        salt.append(&private_salt);
+        let record = Self::open_commit(&env, RevealAuth::Owner, commit_id, &strategy, &salt);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::PublicSalt(commit_id), &public_salt);
+
+        Self::finish_reveal(&env, commit_id, record, strategy);
+    }
+
+    /// The public salt of a commitment revealed via `reveal_two_salt`.
+    pub fn get_public_salt(env: Env, commit_id: u64) -> Option<Bytes> {
+        env.storage().persistent().get(&DataKey::PublicSalt(commit_id))
+    }
+
+    /// The typed strategy for a commitment revealed via `reveal_spec`, or
+    /// `None` if it is unrevealed or was revealed as raw bytes.
+    pub fn get_spec(env: Env, commit_id: u64) -> Option<StrategySpec> {
+        env.storage().persistent().get(&DataKey::Spec(commit_id))
+    }
+
+    /// Place a compliance hold on a commitment. While frozen, `reveal`,
+    /// `cancel`, `rotate_commitment`, `attach_proof` and
+    /// `reveal_proof_in_bundle` fail with `Error::Frozen`. Admin only.
+    pub fn freeze_commitment(env: Env, commit_id: u64) -> Result<(), Error> {
+        Self::set_frozen(&env, commit_id, true)
+    }
+
+    /// Lift a compliance hold placed by `freeze_commitment`. Admin only.
+    pub fn unfreeze_commitment(env: Env, commit_id: u64) -> Result<(), Error> {
+        Self::set_frozen(&env, commit_id, false)
+    }
+
+    /// Cancel an unrevealed commitment, deleting its record. Owner only.
+    pub fn cancel(env: Env, commit_id: u64) {
+        let record: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .unwrap_or_else(|| panic!("commitment not found"));
+
+        record.owner.require_auth();
+
+        if record.revealed {
+            panic!("already revealed");
+        }
+        if record.frozen {
+            panic_with_error!(&env, Error::Frozen);
+        }
+
+        Self::delete_commit(&env, commit_id, &record.owner);
+
+        Self::emit(
+            &env,
+            EVENTS_KEY,
+            (symbol_short!("cancel"),),
+            (commit_id, record.owner),
+        );
+    }
+
+    /// Cancel each of `commit_ids` that exists, belongs to `owner` and is
+    /// unrevealed and not frozen, skipping the rest. Returns how many were
+    /// cancelled.
+    pub fn cancel_batch(env: Env, owner: Address, commit_ids: Vec<u64>) -> u32 {
+        owner.require_auth();
+
+        let mut cancelled = 0;
+        for commit_id in commit_ids.iter() {
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(commit_id));
+            let Some(record) = record else { continue };
+            if record.owner != owner || record.revealed || record.frozen {
+                continue;
+            }
+
+            Self::delete_commit(&env, commit_id, &owner);
+            Self::emit(
+                &env,
+                EVENTS_KEY,
+                (symbol_short!("cancel"),),
+                (commit_id, owner.clone()),
+            );
+            cancelled += 1;
+        }
+        cancelled
+    }
+
+    /// Replace an unrevealed commitment with one using a fresh salt, e.g.
+    /// after the old salt leaked. `continuity_proof` must equal
+    /// sha256(old_commitment || new_commitment). `commit_dual` commitments
+    /// also bind the salt through their Keccak-256 hash and must use
+    /// `rotate_dual_commitment` (`DualCommitment`). Owner only.
+    pub fn rotate_commitment(
+        env: Env,
+        commit_id: u64,
+        new_commitment: BytesN<32>,
+        continuity_proof: BytesN<32>,
+    ) -> Result<(), Error> {
+        Self::rotate(&env, commit_id, new_commitment, None, continuity_proof)
+    }
+
+    /// `rotate_commitment` for a `commit_dual` commitment, replacing its
+    /// Keccak-256 hash with `new_commitment_keccak` alongside. Owner only.
+    pub fn rotate_dual_commitment(
+        env: Env,
+        commit_id: u64,
+        new_commitment: BytesN<32>,
+        new_commitment_keccak: BytesN<32>,
+        continuity_proof: BytesN<32>,
+    ) -> Result<(), Error> {
+        Self::rotate(
+            &env,
+            commit_id,
+            new_commitment,
+            Some(new_commitment_keccak),
+            continuity_proof,
+        )
+    }
+
+    /// True if the commitment is unrevealed and its reveal deadline has
+    /// passed. Commitments without a deadline never expire.
+    pub fn is_expired_commitment(env: Env, commit_id: u64) -> bool {
+        let record: Option<CommitmentRecord> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id));
+
+        match record {
+            Some(record) => !record.revealed && Self::past_deadline(&env, &record),
+            None => false,
+        }
+    }
+
+    /// Delete an unrevealed commitment whose reveal deadline has passed.
+    ///
+    /// Anyone can call this; it only frees storage.
+    pub fn purge_expired_commitment(env: Env, commit_id: u64) {
+        if !Self::is_expired_commitment(env.clone(), commit_id) {
+            panic!("commitment not expired");
+        }
+        Self::purge(&env, commit_id);
+    }
+
+    /// Purge every commitment in `commit_ids` that `is_expired_commitment`,
+    /// skipping the rest. Anyone can call this. Returns how many were purged.
+    pub fn purge_expired_batch(env: Env, commit_ids: Vec<u64>) -> u32 {
+        let mut purged = 0;
+        for commit_id in commit_ids.iter() {
+            if Self::is_expired_commitment(env.clone(), commit_id) {
+                Self::purge(&env, commit_id);
+                purged += 1;
+            }
+        }
+        purged
+    }
+
+    // ─── Proof Attachments ──────────────────────────────────────────────
+
+    /// Attach a proof hash on-chain, linked to an existing commitment and a trade tx.
+    ///
+    /// `proof_hash` = SHA-256(strategy || trade_params || salt), computed off-chain.
+    /// Returns the proof_id.
+    pub fn attach_proof(
+        env: Env,
+        owner: Address,
+        proof_hash: BytesN<32>,
+        commit_id: u64,
+        tx_hash: Bytes,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+
+        // Validate the commitment exists and belongs to the caller
+        let commit: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .ok_or(Error::CommitNotFound)?;
+
+        if commit.owner != owner {
+            return Err(Error::NotOwner);
+        }
+        if commit.frozen {
+            return Err(Error::Frozen);
+        }
+
+        Ok(Self::store_proof(&env, owner, proof_hash, commit_id, tx_hash))
+    }
+
+    /// Create a ProofRecord for a member of the commitment's proof bundle.
+    /// `merkle_proof` is the sibling path from `proof_hash`'s leaf up to the
+    /// committed `proof_root` (see `commit_with_proof_root` for the tree
+    /// layout). Returns the proof_id.
+    pub fn reveal_proof_in_bundle(
+        env: Env,
+        commit_id: u64,
+        proof_hash: BytesN<32>,
+        merkle_proof: Vec<BytesN<32>>,
+    ) -> Result<u64, Error> {
+        let commit: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .ok_or(Error::CommitNotFound)?;
+
+        commit.owner.require_auth();
+
+        if commit.frozen {
+            return Err(Error::Frozen);
+        }
+        let root = commit.proof_root.ok_or(Error::NoProofRoot)?;
+        let mut leaf = Bytes::from_array(&env, &[0u8]);
+        leaf.append(&proof_hash.clone().into());
+        let mut node: BytesN<32> = env.crypto().sha256(&leaf).into();
+        for sibling in merkle_proof.iter() {
+            let mut pair = Bytes::from_array(&env, &[1u8]);
+            if node.to_array() <= sibling.to_array() {
+                pair.append(&node.clone().into());
+                pair.append(&sibling.into());
+            } else {
+                pair.append(&sibling.into());
+                pair.append(&node.clone().into());
+            }
+            node = env.crypto().sha256(&pair).into();
+        }
+        if Bytes::from(node) != root {
+            return Err(Error::NotInBundle);
+        }
+
+        Ok(Self::store_proof(
+            &env,
+            commit.owner,
+            proof_hash,
+            commit_id,
+            Bytes::new(&env),
+        ))
+    }
+
+    /// Annotate a revealed proof with its realized PnL (in the trade's
+    /// quote units; negative for a loss). Re-recording overwrites. Owner
+    /// only.
+    pub fn record_pnl(env: Env, proof_id: u64, pnl: i128) -> Result<(), Error> {
+        let key = DataKey::Proof(proof_id);
+        let mut record: ProofRecord = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(Error::ProofNotFound)?;
+
+        record.owner.require_auth();
+
+        if !record.revealed {
+            return Err(Error::ProofNotRevealed);
+        }
+
+        record.pnl = Some(pnl);
+        env.storage().persistent().set(&key, &record);
+
+        Self::emit(&env, EVENTS_VERBOSE, (symbol_short!("pnl"),), (proof_id, pnl));
+        Ok(())
+    }
+
+    /// Realized PnL recorded for a proof, if any.
+    pub fn get_pnl(env: Env, proof_id: u64) -> Option<i128> {
+        let record: Option<ProofRecord> = env.storage().persistent().get(&DataKey::Proof(proof_id));
+        record.and_then(|r| r.pnl)
+    }
+
+    /// Read a proof record by ID.
+    pub fn get_proof(env: Env, proof_id: u64) -> ProofRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Proof(proof_id))
+            .unwrap_or_else(|| panic!("proof not found"))
+    }
+
+    /// First proof attached with `tx_hash`, if any. See `proofs_by_tx` for
+    /// all of them.
+    pub fn find_proof_by_tx(env: Env, tx_hash: Bytes) -> Option<u64> {
+        Self::proofs_by_tx(env, tx_hash).first()
+    }
+
+    /// Every proof attached with `tx_hash`, in attach order.
+    pub fn proofs_by_tx(env: Env, tx_hash: Bytes) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ProofByTx(tx_hash))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// True if the proof exists and its `commit_id` points at an existing
+    /// commitment owned by the same address.
+    pub fn verify_proof_linkage(env: Env, proof_id: u64) -> bool {
+        let proof: Option<ProofRecord> = env.storage().persistent().get(&DataKey::Proof(proof_id));
+        let Some(proof) = proof else {
+            return false;
+        };
+
+        let commit: Option<CommitmentRecord> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(proof.commit_id));
+        match commit {
+            Some(commit) => commit.owner == proof.owner,
+            None => false,
+        }
+    }
+
+    /// Reveal a proof: prove that hash(strategy || trade_params || salt) == proof_hash.
+    ///
+    /// On success, stores plaintext strategy and trade_params, marks revealed.
+    pub fn reveal_proof(
+        env: Env,
+        proof_id: u64,
+        strategy: Bytes,
+        trade_params: Bytes,
+        salt: Bytes,
+    ) {
+        Self::apply_proof_reveal(&env, proof_id, strategy, trade_params, salt);
+    }
+
+    /// Reveal a proof and, with the same `strategy` plus `commit_salt`, its
+    /// linked commitment in one call. The commitment step is skipped if it
+    /// is already revealed; if its hash does not match, the whole call
+    /// reverts and the proof stays unrevealed.
+    pub fn reveal_proof_and_commit(
+        env: Env,
+        proof_id: u64,
+        strategy: Bytes,
+        trade_params: Bytes,
+        salt: Bytes,
+        commit_salt: Bytes,
+    ) {
+        let proof = Self::apply_proof_reveal(&env, proof_id, strategy.clone(), trade_params, salt);
+
+        let commit: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(proof.commit_id))
+            .unwrap_or_else(|| panic!("commitment not found"));
+        if !commit.revealed {
+            let record =
+                Self::open_commit(&env, RevealAuth::Owner, proof.commit_id, &strategy, &commit_salt);
+            Self::finish_reveal(&env, proof.commit_id, record, strategy);
+        }
+    }
+
+    /// sha256(strategy || salt): the commitment `reveal` checks for the
+    /// default `Sha256`/`Concat` record. Meant to be called via simulation
+    /// so clients build the preimage exactly as the contract does.
+    pub fn compute_commitment(env: Env, strategy: Bytes, salt: Bytes) -> BytesN<32> {
+        env.crypto().sha256(&Self::preimage(&env, &strategy, &salt)).into()
+    }
+
+    /// sha256(seed || index as 8 big-endian bytes): the `index`-th salt of a
+    /// master `seed`, so clients can regenerate salts instead of storing
+    /// them. Pure helper; nothing is stored.
+    pub fn derive_salt(env: Env, seed: Bytes, index: u64) -> BytesN<32> {
+        let mut preimage = seed;
+        preimage.extend_from_array(&index.to_be_bytes());
+        env.crypto().sha256(&preimage).into()
+    }
+
+    /// Approximate bytes a `reveal` writes for a strategy of `strategy_len`
+    /// bytes: the record overhead plus the strategy padded to XDR's 4-byte
+    /// alignment. For wallet fee pre-flight only.
+    pub fn estimate_reveal_footprint(strategy_len: u32) -> u32 {
+        let padded = strategy_len.saturating_add(3) & !3;
+        REVEAL_FOOTPRINT_OVERHEAD.saturating_add(padded)
+    }
+
+    /// Liveness probe for monitoring. Returns `(ledger_sequence, timestamp)`.
+    pub fn heartbeat(env: Env) -> (u32, u64) {
+        let ledger = env.ledger().sequence();
+        let timestamp = env.ledger().timestamp();
+        Self::emit(&env, EVENTS_VERBOSE, (symbol_short!("beat"),), (ledger, timestamp));
+        (ledger, timestamp)
+    }
+
+    // ─── Listing ────────────────────────────────────────────────────────
+
+    /// Largest page any `list_*` call returns. Larger limits are clamped.
+    pub fn max_page_size() -> u32 {
+        MAX_PAGE_SIZE
+    }
+
+    /// Number of live commitments in `owner`'s index, for sizing
+    /// `list_commitments` pages.
+    pub fn commitment_count(env: Env, owner: Address) -> u32 {
+        let ids: Option<Vec<u64>> = env.storage().persistent().get(&DataKey::OwnerCommits(owner));
+        ids.map_or(0, |ids| ids.len())
+    }
+
+    /// Number of `owner`'s commitments still unrevealed ("open positions").
+    /// Only the most recent `MAX_SCAN` entries of the owner's index are
+    /// checked.
+    pub fn open_commitments(env: Env, owner: Address) -> u32 {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerCommits(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+        let start = ids.len().saturating_sub(MAX_SCAN);
+
+        let mut open = 0;
+        for id in ids.slice(start..).iter() {
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            if record.is_some_and(|r| !r.revealed) {
+                open += 1;
+            }
+        }
+        open
+    }
+
+    /// The owner's most recent commitment that has not been cancelled or
+    /// purged.
+    pub fn latest_commitment(env: Env, owner: Address) -> Option<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::LatestCommit(owner))
+    }
+
+    /// Id of the owner's unrevealed commitment opened by `strategy` and
+    /// `salt`, if any. Only the most recent `MAX_SCAN` entries of the
+    /// owner's index are checked.
+    pub fn find_matching_commitment(
+        env: Env,
+        owner: Address,
+        strategy: Bytes,
+        salt: Bytes,
+    ) -> Option<u64> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerCommits(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+        let start = ids.len().saturating_sub(MAX_SCAN);
+
+        for id in ids.slice(start..).iter() {
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            if let Some(record) = record {
+                if !record.revealed
+                    && Self::digest(&env, &record, &strategy, &salt) == record.commitment
+                {
+                    return Some(id);
+                }
+            }
+        }
+        None
+    }
+
+    /// The `revealed` flag for each id in `ids`, in order, with `false` for
+    /// ids that don't exist. Only the first `MAX_PAGE_SIZE` ids are read.
+    pub fn reveal_statuses(env: Env, ids: Vec<u64>) -> Vec<bool> {
+        let mut out = Vec::new(&env);
+        for id in ids.iter().take(MAX_PAGE_SIZE as usize) {
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            out.push_back(record.is_some_and(|record| record.revealed));
+        }
+        out
+    }
+
+    /// Page through `owner`'s revealed strategies as `(commit_id, strategy)`,
+    /// in commit order from commit id `start`. Scans at most `MAX_SCAN`
+    /// index entries and stops at `limit` (capped at `MAX_PAGE_SIZE`)
+    /// results or once `MAX_PAGE_BYTES` of strategy would be exceeded
+    /// (always returning at least one). Resume from the last id + 1.
+    pub fn revealed_strategies(
+        env: Env,
+        owner: Address,
+        start: u64,
+        limit: u32,
+    ) -> Vec<(u64, Bytes)> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerCommits(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+        let limit = limit.min(MAX_PAGE_SIZE);
+
+        let mut page = Vec::new(&env);
+        let mut bytes: u32 = 0;
+        for id in ids.iter().filter(|id| *id >= start).take(MAX_SCAN as usize) {
+            if page.len() >= limit {
+                break;
+            }
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            if let Some(record) = record.filter(|record| record.revealed) {
+                let len = record.strategy.len();
+                if !page.is_empty() && bytes + len > MAX_PAGE_BYTES {
+                    break;
+                }
+                bytes += len;
+                page.push_back((id, record.strategy));
+            }
+        }
+        page
+    }
+
+    /// Page through `owner`'s commitments carrying all (`match_all`) or any
+    /// of `tags`, in commit order from position `start` of their index.
+    /// Scans at most `MAX_SCAN` index entries and returns up to `limit`
+    /// (capped at `MAX_PAGE_SIZE`) matches.
+    pub fn list_by_tags(
+        env: Env,
+        owner: Address,
+        tags: Vec<Symbol>,
+        match_all: bool,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(u64, CommitmentRecord)> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerCommits(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+        let limit = limit.min(MAX_PAGE_SIZE);
+
+        let mut page = Vec::new(&env);
+        for id in ids.iter().skip(start as usize).take(MAX_SCAN as usize) {
+            if page.len() >= limit {
+                break;
+            }
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            if let Some(record) = record {
+                let matched = if match_all {
+                    tags.iter().all(|tag| record.tags.contains(&tag))
+                } else {
+                    tags.iter().any(|tag| record.tags.contains(&tag))
+                };
+                if matched {
+                    page.push_back((id, record));
+                }
+            }
+        }
+        page
+    }
+
+    /// Page through `owner`'s commitments in commit order, starting at
+    /// position `start` of their index.
+    pub fn list_commitments(
+        env: Env,
+        owner: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(u64, CommitmentRecord)> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerCommits(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut page = Vec::new(&env);
+        for id in ids.iter().skip(start as usize).take(limit.min(MAX_PAGE_SIZE) as usize) {
+            if let Some(record) = env.storage().persistent().get(&DataKey::Commitment(id)) {
+                page.push_back((id, record));
+            }
+        }
+        page
+    }
+
+    /// Ids of live commitments whose hash is `commitment`, in commit order.
+    /// Cancelled, purged and rotated-away ids are left out. Only the first
+    /// `MAX_SCAN` ids ever indexed under a hash are considered.
+    pub fn find_by_hash(env: Env, commitment: BytesN<32>) -> Vec<u64> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::CommitByHash(commitment.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut out = Vec::new(&env);
+        for id in ids.iter() {
+            let record: Option<CommitmentRecord> =
+                env.storage().persistent().get(&DataKey::Commitment(id));
+            if record.is_some_and(|r| r.commitment == commitment) {
+                out.push_back(id);
+            }
+        }
+        out
+    }
+
+    /// Ids committed on `day` (ledger timestamp / 86400), in commit order,
+    /// up to `MAX_DAY_BUCKET`. Includes commitments later cancelled or
+    /// purged.
+    pub fn commits_on_day(env: Env, day: u64) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::CommitsByDay(day))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Page through all commitments by global id, across owners. Covers ids
+    /// `start..start + limit` (limit capped at `MAX_PAGE_SIZE`); cancelled
+    /// or purged ids are skipped.
+    pub fn feed(env: Env, start: u64, limit: u32) -> Vec<(u64, CommitmentRecord)> {
+        let next: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextId)
+            .unwrap_or(0);
+        let end = next.min(start.saturating_add(limit.min(MAX_PAGE_SIZE) as u64));
+
+        let mut page = Vec::new(&env);
+        for id in start..end {
+            if let Some(record) = env.storage().persistent().get(&DataKey::Commitment(id)) {
+                page.push_back((id, record));
+            }
+        }
+        page
+    }
+
+    /// Page through `owner`'s proofs in attach order, starting at position
+    /// `start` of their index.
+    pub fn list_proofs(
+        env: Env,
+        owner: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(u64, ProofRecord)> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OwnerProofs(owner))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut page = Vec::new(&env);
+        for id in ids.iter().skip(start as usize).take(limit.min(MAX_PAGE_SIZE) as usize) {
+            if let Some(record) = env.storage().persistent().get(&DataKey::Proof(id)) {
+                page.push_back((id, record));
+            }
+        }
+        page
+    }
+
+    // ─── Internal ───────────────────────────────────────────────────────
+
+    /// Delete a commitment and its indexes, emitting the purge event.
+    fn purge(env: &Env, commit_id: u64) {
+        let record = Self::get(env.clone(), commit_id);
+        Self::delete_commit(env, commit_id, &record.owner);
+
+        Self::emit(env, EVENTS_VERBOSE, (symbol_short!("purge"),), commit_id);
+    }
+
+    /// Remove a commitment record, its side keys and its index entry.
+    fn delete_commit(env: &Env, commit_id: u64, owner: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Commitment(commit_id));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::RevealAcl(commit_id));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::CompressedHash(commit_id));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::RevealIntent(commit_id));
+        Self::unindex_commit(env, owner, commit_id);
+    }
+
+    /// Drop a deleted commitment from its owner's index.
+    fn unindex_commit(env: &Env, owner: &Address, commit_id: u64) {
+        let index_key = DataKey::OwnerCommits(owner.clone());
+        let mut ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or_else(|| Vec::new(env));
+        if let Some(pos) = ids.first_index_of(commit_id) {
+            ids.remove(pos);
+            env.storage().persistent().set(&index_key, &ids);
+        }
+
+        // Fall back to the newest remaining commitment
+        let latest_key = DataKey::LatestCommit(owner.clone());
+        match ids.last() {
+            Some(latest) => env.storage().persistent().set(&latest_key, &latest),
+            None => env.storage().persistent().remove(&latest_key),
+        }
+    }
+
+    /// A fresh, unrevealed record with no optional features set.
+    fn new_record(env: &Env, owner: Address, commitment: BytesN<32>) -> CommitmentRecord {
+        CommitmentRecord {
+            owner,
+            commitment,
+            revealed: false,
+            strategy: Bytes::new(env),
+            timestamp: env.ledger().timestamp(),
+            expires_at: None,
+            hash_alg: HashAlg::Sha256,
+            proof_root: None,
+            scheme: CommitScheme::Concat,
+            frozen: false,
+            parent_id: None,
+            valid_from: None,
+            valid_to: None,
+            keccak_commitment: None,
+            compressed: false,
+            tags: Vec::new(env),
+            verifier: None,
+        }
+    }
+
+    /// Load a commitment for reveal and check that hash(strategy || salt)
+    /// matches. Requires auth as described by `auth`.
+    fn open_commit(
+        env: &Env,
+        auth: RevealAuth,
+        commit_id: u64,
+        strategy: &Bytes,
+        salt: &Bytes,
+    ) -> CommitmentRecord {
+        let record: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Commitment(commit_id))
+            .unwrap_or_else(|| panic!("commitment not found"));
+
+        // Only the owner (or an ACL member, or the admin) can reveal
+        match auth {
+            RevealAuth::Owner => record.owner.require_auth(),
+            RevealAuth::Delegate(revealer) => {
+                if *revealer != record.owner
+                    && !Self::reveal_acl(env.clone(), commit_id).contains(revealer)
+                {
+                    panic_with_error!(env, Error::NotRevealer);
+                }
+                revealer.require_auth();
+            }
+            RevealAuth::Admin => {}
+            RevealAuth::Keeper(keeper) => keeper.require_auth(),
+        }
+
+        if record.revealed {
+            panic!("already revealed");
+        }
+
+        if record.frozen {
+            panic_with_error!(env, Error::Frozen);
+        }
+
+        if Self::strict_proofs(env.clone()) && Self::has_pending_proofs(env, commit_id) {
+            panic_with_error!(env, Error::PendingProofs);
+        }
+
+        if Self::past_deadline(env, &record) {
+            panic!("reveal deadline passed");
+        }
+
+        let now = env.ledger().timestamp();
+        if record.valid_from.is_some_and(|from| now < from) {
+            panic_with_error!(env, Error::NotYetValid);
+        }
+        if record.valid_to.is_some_and(|to| now > to) {
+            panic_with_error!(env, Error::Expired);
+        }
+
+        // The contract only ever sees the salt at reveal, so enforce the
+        // entropy policy here.
+        if salt.len() < Self::min_salt_len(env.clone()) {
+            panic_with_error!(env, Error::WeakSalt);
+        }
+
+        if Self::digest(env, &record, strategy, salt) != record.commitment {
+            panic!("hash mismatch");
+        }
+
+        if let Some(keccak_commitment) = &record.keccak_commitment {
+            let mut preimage = Bytes::new(env);
+            preimage.append(strategy);
+            preimage.append(salt);
+            let computed: BytesN<32> = env.crypto().keccak256(&preimage).into();
+            if Bytes::from(computed) != *keccak_commitment {
+                panic!("hash mismatch");
+            }
+        }
+
+        if let Some(verifier) = &record.verifier {
+            let trade_params = Bytes::new(env);
+            if !VerifierClient::new(env, verifier).verify(strategy, &trade_params) {
+                panic_with_error!(env, Error::VerifierRejected);
+            }
+        }
+
+        // Charge the reveal fee before anything is marked revealed; a failed
+        // transfer reverts the whole reveal.
+        let payer = match auth {
+            RevealAuth::Owner => Some(&record.owner),
+            RevealAuth::Delegate(revealer) => Some(revealer),
+            RevealAuth::Keeper(keeper) => Some(keeper),
+            RevealAuth::Admin => None,
+        };
+        if let (Some(payer), Some(fee)) = (payer, Self::reveal_fee(env.clone())) {
+            token::Client::new(env, &fee.token).transfer(payer, &fee.collector, &fee.amount);
+        }
+
+        record
+    }
+
+    /// Recompute the commitment for `record`'s scheme and hash algorithm.
+    fn digest(env: &Env, record: &CommitmentRecord, strategy: &Bytes, salt: &Bytes) -> BytesN<32> {
+        match record.scheme {
+            CommitScheme::Hmac => Self::hmac_sha256(env, salt, strategy),
+            CommitScheme::Concat => {
+                // Reconstruct: hash(strategy || salt)
+                let preimage = Self::preimage(env, strategy, salt);
+                match record.hash_alg {
+                    HashAlg::Sha256 => env.crypto().sha256(&preimage).into(),
+                    HashAlg::Poseidon => poseidon::hash(env, &preimage),
+                }
+            }
+        }
+    }
+
+    /// strategy || salt, the `Concat` scheme's hash input.
+    fn preimage(env: &Env, strategy: &Bytes, salt: &Bytes) -> Bytes {
+        let mut preimage = Bytes::new(env);
+        preimage.append(strategy);
+        preimage.append(salt);
+        preimage
+    }
+
+    /// HMAC-SHA256 (RFC 2104): sha256((k ^ opad) || sha256((k ^ ipad) || msg)),
+    /// with keys longer than the 64-byte block hashed first.
+    fn hmac_sha256(env: &Env, key: &Bytes, msg: &Bytes) -> BytesN<32> {
+        let mut block = [0u8; 64];
+        if key.len() > 64 {
+            let digest: BytesN<32> = env.crypto().sha256(key).into();
+            block[..32].copy_from_slice(&digest.to_array());
+        } else {
+            key.copy_into_slice(&mut block[..key.len() as usize]);
+        }
+
+        let mut ipad = [0u8; 64];
+        let mut opad = [0u8; 64];
+        for i in 0..64 {
+            ipad[i] = block[i] ^ 0x36;
+            opad[i] = block[i] ^ 0x5c;
+        }
+
+        let mut inner = Bytes::from_array(env, &ipad);
+        inner.append(msg);
+        let inner_hash: BytesN<32> = env.crypto().sha256(&inner).into();
+
+        let mut outer = Bytes::from_array(env, &opad);
+        outer.append(&inner_hash.into());
+        env.crypto().sha256(&outer).into()
+    }
+
+    /// Mark a verified commitment revealed, store it and emit the reveal event.
+    fn finish_reveal(env: &Env, commit_id: u64, mut record: CommitmentRecord, strategy: Bytes) {
+        record.revealed = true;
+        record.strategy = strategy;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Commitment(commit_id), &record);
+
+        // Emit event
+        Self::emit(
+            env,
+            EVENTS_KEY,
+            (symbol_short!("reveal"),),
+            (commit_id, record.owner),
+        );
+    }
+
+    /// Verify hash(strategy || trade_params || salt) against a proof,
+    /// store the plaintext and emit the reveal event. Requires owner auth.
+    fn apply_proof_reveal(
+        env: &Env,
+        proof_id: u64,
+        strategy: Bytes,
+        trade_params: Bytes,
+        salt: Bytes,
+    ) -> ProofRecord {
+        let mut record: ProofRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proof(proof_id))
+            .unwrap_or_else(|| panic!("proof not found"));
+
+        record.owner.require_auth();
+
+        if record.revealed {
+            panic!("already revealed");
+        }
+
+        // Reconstruct: hash(strategy || trade_params || salt)
+        let mut preimage = Bytes::new(env);
+        preimage.append(&strategy);
         preimage.append(&trade_params);
         preimage.append(&salt);
 
-        let computed: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let computed: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        if computed != record.proof_hash {
+            panic!("proof hash mismatch");
+        }
+
+        record.revealed = true;
+        record.strategy = strategy;
+        record.trade_params = trade_params;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Proof(proof_id), &record);
+
+        Self::emit(
+            env,
+            EVENTS_VERBOSE,
+            (symbol_short!("p_reveal"),),
+            (proof_id, record.owner.clone()),
+        );
+
+        record
+    }
+
+    /// Store a new commitment under the next id and emit the commit event.
+    fn store_commit(env: &Env, record: CommitmentRecord) -> u64 {
+        // Auto-increment ID
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextId)
+            .unwrap_or(0);
+
+        let key = DataKey::Commitment(id);
+        env.storage().persistent().set(&key, &record);
+        env.storage().persistent().extend_ttl(&key, 518_400, 518_400);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::NextId, &(id + 1));
+
+        let index_key = DataKey::OwnerCommits(record.owner.clone());
+        let mut ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or_else(|| Vec::new(env));
+        ids.push_back(id);
+        env.storage().persistent().set(&index_key, &ids);
+        env.storage()
+            .persistent()
+            .set(&DataKey::LatestCommit(record.owner.clone()), &id);
+
+        let day_key = DataKey::CommitsByDay(record.timestamp / 86_400);
+        let mut day: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&day_key)
+            .unwrap_or_else(|| Vec::new(env));
+        if day.len() < MAX_DAY_BUCKET {
+            day.push_back(id);
+            env.storage().persistent().set(&day_key, &day);
+        }
+        Self::index_hash(env, &record.commitment, id);
+
+        // Emit event
+        Self::emit(env, EVENTS_KEY, (symbol_short!("commit"),), (id, record.owner.clone()));
+
+        let index_key = DataKey::Expecters(record.owner.clone(), record.commitment.clone());
+        let expecters: Option<Vec<Address>> = env.storage().persistent().get(&index_key);
+        if let Some(expecters) = expecters {
+            env.storage().persistent().remove(&index_key);
+            for registrant in expecters.iter() {
+                env.storage().persistent().remove(&DataKey::Expectation(
+                    registrant.clone(),
+                    record.owner.clone(),
+                    record.commitment.clone(),
+                ));
+                Self::emit(
+                    env,
+                    EVENTS_KEY,
+                    (symbol_short!("exp_met"),),
+                    (id, record.owner.clone(), record.commitment.clone(), registrant),
+                );
+            }
+        }
+
+        id
+    }
+
+    /// Swap an unrevealed commitment's hash(es) after checking the
+    /// continuity proof. Dual commitments must be rotated together with
+    /// their Keccak-256 hash.
+    fn rotate(
+        env: &Env,
+        commit_id: u64,
+        new_commitment: BytesN<32>,
+        new_commitment_keccak: Option<BytesN<32>>,
+        continuity_proof: BytesN<32>,
+    ) -> Result<(), Error> {
+        let key = DataKey::Commitment(commit_id);
+        let mut record: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(Error::NotFound)?;
+
+        record.owner.require_auth();
+
+        if record.revealed {
+            return Err(Error::AlreadyRevealed);
+        }
+        if record.frozen {
+            return Err(Error::Frozen);
+        }
+        if record.keccak_commitment.is_some() != new_commitment_keccak.is_some() {
+            return Err(Error::DualCommitment);
+        }
+
+        let mut link = Bytes::new(env);
+        link.append(&record.commitment.clone().into());
+        link.append(&new_commitment.clone().into());
+        let expected: BytesN<32> = env.crypto().sha256(&link).into();
+        if expected != continuity_proof {
+            return Err(Error::BadContinuityProof);
+        }
+
+        let old = record.commitment;
+        record.commitment = new_commitment.clone();
+        if let Some(keccak) = new_commitment_keccak {
+            record.keccak_commitment = Some(keccak.into());
+        }
+        env.storage().persistent().set(&key, &record);
+        Self::index_hash(env, &new_commitment, commit_id);
+
+        Self::emit(
+            env,
+            EVENTS_KEY,
+            (symbol_short!("rotate"),),
+            (commit_id, old, new_commitment),
+        );
+        Ok(())
+    }
+
+    /// Record `id` under its commitment hash, up to `MAX_SCAN` ids per hash.
+    fn index_hash(env: &Env, commitment: &BytesN<32>, id: u64) {
+        let key = DataKey::CommitByHash(commitment.clone());
+        let mut ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| Vec::new(env));
+        if ids.len() < MAX_SCAN {
+            ids.push_back(id);
+            env.storage().persistent().set(&key, &ids);
+        }
+    }
+
+    /// Assign the next proof_id, store the record and index it.
+    fn store_proof(
+        env: &Env,
+        owner: Address,
+        proof_hash: BytesN<32>,
+        commit_id: u64,
+        tx_hash: Bytes,
+    ) -> u64 {
+        // Auto-increment proof ID
+        let proof_id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextProofId)
+            .unwrap_or(0);
+
+        if !tx_hash.is_empty() {
+            let tx_key = DataKey::ProofByTx(tx_hash.clone());
+            let mut by_tx: Vec<u64> = env
+                .storage()
+                .persistent()
+                .get(&tx_key)
+                .unwrap_or_else(|| Vec::new(env));
+            by_tx.push_back(proof_id);
+            env.storage().persistent().set(&tx_key, &by_tx);
+        }
+
+        let record = ProofRecord {
+            owner: owner.clone(),
+            proof_hash,
+            commit_id,
+            tx_hash,
+            revealed: false,
+            strategy: Bytes::new(env),
+            trade_params: Bytes::new(env),
+            timestamp: env.ledger().timestamp(),
+            pnl: None,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Proof(proof_id), &record);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ProofByCommit(commit_id), &proof_id);
+
+        let commit_key = DataKey::CommitProofs(commit_id);
+        let mut attached: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&commit_key)
+            .unwrap_or_else(|| Vec::new(env));
+        attached.push_back(proof_id);
+        env.storage().persistent().set(&commit_key, &attached);
+
+        let index_key = DataKey::OwnerProofs(owner.clone());
+        let mut proofs: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or_else(|| Vec::new(env));
+        proofs.push_back(proof_id);
+        env.storage().persistent().set(&index_key, &proofs);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::NextProofId, &(proof_id + 1));
+
+        Self::emit(
+            env,
+            EVENTS_VERBOSE,
+            (symbol_short!("proof"),),
+            (proof_id, owner, commit_id),
+        );
+
+        proof_id
+    }
+
+    fn has_pending_proofs(env: &Env, commit_id: u64) -> bool {
+        let attached: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::CommitProofs(commit_id))
+            .unwrap_or_else(|| Vec::new(env));
+        attached.iter().any(|proof_id| {
+            let proof: Option<ProofRecord> =
+                env.storage().persistent().get(&DataKey::Proof(proof_id));
+            proof.is_some_and(|p| !p.revealed)
+        })
+    }
+
+    fn past_deadline(env: &Env, record: &CommitmentRecord) -> bool {
+        match record.expires_at {
+            Some(expires_at) => env.ledger().timestamp() > expires_at,
+            None => false,
+        }
+    }
+
+    fn set_frozen(env: &Env, commit_id: u64, frozen: bool) -> Result<(), Error> {
+        Self::require_admin(env);
+
+        let key = DataKey::Commitment(commit_id);
+        let mut record: CommitmentRecord = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(Error::NotFound)?;
+        record.frozen = frozen;
+        env.storage().persistent().set(&key, &record);
+
+        let topic = if frozen { symbol_short!("freeze") } else { symbol_short!("unfreeze") };
+        Self::emit(env, EVENTS_KEY, (topic,), commit_id);
+        Ok(())
+    }
+
+    fn require_admin(env: &Env) -> Address {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic!("not initialized"));
+        admin.require_auth();
+        admin
+    }
+
+    /// Publish an event if the configured verbosity is at least `level`.
+    fn emit<T: Topics, D: IntoVal<Env, Val>>(env: &Env, level: u32, topics: T, data: D) {
+        if Self::event_level(env.clone()) >= level {
+            env.events().publish(topics, data);
+        }
+    }
+}
+
+// ─── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger},
+        Env,
+    };
+
+    #[test]
+    fn test_commit_get_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // Build commitment off-chain: sha256(strategy || salt)
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        // 1. Commit
+        let id = client.commit(&owner, &commitment);
+        assert_eq!(id, 0);
+
+        // 2. Get
+        let record = client.get(&id);
+        assert_eq!(record.owner, owner);
+        assert_eq!(record.commitment, commitment);
+        assert!(!record.revealed);
+
+        // 3. Reveal
+        client.reveal(&id, &strategy, &salt);
+
+        let revealed = client.get(&id);
+        assert!(revealed.revealed);
+        assert_eq!(revealed.strategy, strategy);
+    }
+
+    #[test]
+    #[should_panic(expected = "hash mismatch")]
+    fn test_bad_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+
+        // Try reveal with wrong salt
+        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
+        client.reveal(&id, &strategy, &bad_salt);
+    }
+
+    #[test]
+    fn test_attach_and_get_proof() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // First create a commitment
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let commit_id = client.commit(&owner, &commitment);
+
+        // Build proof hash: sha256(strategy || trade_params || proof_salt)
+        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
+        let mut proof_preimage = Bytes::new(&env);
+        proof_preimage.append(&strategy);
+        proof_preimage.append(&trade_params);
+        proof_preimage.append(&proof_salt);
+        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+
+        // Attach proof
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+        assert_eq!(proof_id, 0);
+
+        // Get proof
+        let record = client.get_proof(&proof_id);
+        assert_eq!(record.owner, owner);
+        assert_eq!(record.proof_hash, proof_hash);
+        assert_eq!(record.commit_id, commit_id);
+        assert!(!record.revealed);
+    }
+
+    #[test]
+    fn test_reveal_proof() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // Create commitment
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let commit_id = client.commit(&owner, &commitment);
+
+        // Build proof
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
+        let mut proof_preimage = Bytes::new(&env);
+        proof_preimage.append(&strategy);
+        proof_preimage.append(&trade_params);
+        proof_preimage.append(&proof_salt);
+        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+
+        // Reveal
+        client.reveal_proof(&proof_id, &strategy, &trade_params, &proof_salt);
+
+        let record = client.get_proof(&proof_id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, strategy);
+        assert_eq!(record.trade_params, trade_params);
+    }
+
+    #[test]
+    #[should_panic(expected = "proof hash mismatch")]
+    fn test_bad_reveal_proof() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let commit_id = client.commit(&owner, &commitment);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
+        let mut proof_preimage = Bytes::new(&env);
+        proof_preimage.append(&strategy);
+        proof_preimage.append(&trade_params);
+        proof_preimage.append(&proof_salt);
+        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+
+        // Reveal with wrong salt
+        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
+        client.reveal_proof(&proof_id, &strategy, &trade_params, &bad_salt);
+    }
+
+    #[test]
+    fn test_multiple_commits() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+
+        let id0 = client.commit(&owner, &commitment);
+        let id1 = client.commit(&owner, &commitment);
+        let id2 = client.commit(&owner, &commitment);
+
+        assert_eq!(id0, 0);
+        assert_eq!(id1, 1);
+        assert_eq!(id2, 2);
+    }
+
+    #[test]
+    fn test_event_level_key_only() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        client.init(&admin);
+        client.set_event_level(&EVENTS_KEY);
+
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let commit_id = client.commit(&owner, &commitment);
+        let after_commit = env.events().all().len();
+        assert!(after_commit > 0);
+
+        // Proof attachments are verbose-only
+        let proof_hash: BytesN<32> = BytesN::from_array(&env, &[2u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+        assert_eq!(env.events().all().len(), after_commit);
+
+        client.set_event_level(&EVENTS_NONE);
+        client.commit(&owner, &commitment);
+        assert_eq!(env.events().all().len(), after_commit);
+    }
+
+    #[test]
+    fn test_purge_expired_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+
+        env.ledger().set_timestamp(1_000);
+        let id = client.commit_with_deadline(&owner, &commitment, &2_000);
+        let no_deadline = client.commit(&owner, &commitment);
+
+        assert!(!client.is_expired_commitment(&id));
+        assert!(client.try_purge_expired_commitment(&id).is_err());
+
+        // Advance past the deadline
+        env.ledger().set_timestamp(2_001);
+        assert!(client.is_expired_commitment(&id));
+        assert!(!client.is_expired_commitment(&no_deadline));
+
+        client.purge_expired_commitment(&id);
+        assert!(client.try_get(&id).is_err());
+        assert!(!client.is_expired_commitment(&id));
+
+        // Commitments without a deadline are never purgeable
+        assert!(client.try_purge_expired_commitment(&no_deadline).is_err());
+    }
+
+    #[test]
+    fn test_verify_proof_linkage() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let proof_hash: BytesN<32> = BytesN::from_array(&env, &[2u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+
+        let commit_id = client.commit(&owner, &commitment);
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+        assert!(client.verify_proof_linkage(&proof_id));
+
+        // Missing proof
+        assert!(!client.verify_proof_linkage(&99));
+
+        // Cancelled commitment breaks the linkage
+        client.cancel(&commit_id);
+        assert!(client.try_get(&commit_id).is_err());
+        assert!(!client.verify_proof_linkage(&proof_id));
+    }
+
+    #[test]
+    fn test_reveal_spec() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // Commit to sha256(spec_xdr || salt)
+        let spec = StrategySpec {
+            asset: Symbol::new(&env, "XLM"),
+            side: Symbol::new(&env, "buy"),
+            threshold: 30,
+        };
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let spec_xdr = spec.clone().to_xdr(&env);
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&spec_xdr);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+
+        // A different threshold does not match
+        let mut wrong = spec.clone();
+        wrong.threshold = 70;
+        assert!(client.try_reveal_spec(&id, &wrong, &salt).is_err());
+
+        client.reveal_spec(&id, &spec, &salt);
+
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, spec_xdr);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_reveal_weak_salt() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        client.init(&admin);
+        client.set_min_salt_len(&16);
+
+        // 8-byte salt matches the commitment but is below the policy
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"shortslt");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+        client.reveal(&id, &strategy, &salt);
+    }
+
+    #[test]
+    fn test_attach_proof_not_owner() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let other = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let proof_hash: BytesN<32> = BytesN::from_array(&env, &[2u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+
+        let commit_id = client.commit(&owner, &commitment);
+
+        assert_eq!(
+            client.try_attach_proof(&other, &proof_hash, &commit_id, &tx_hash),
+            Err(Ok(Error::NotOwner))
+        );
+        assert_eq!(
+            client.try_attach_proof(&owner, &proof_hash, &99, &tx_hash),
+            Err(Ok(Error::CommitNotFound))
+        );
+    }
+
+    #[test]
+    fn test_reveal_proof_and_commit() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let commit_salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&commit_salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let commit_id = client.commit(&owner, &commitment);
+
+        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
+        let mut proof_preimage = Bytes::new(&env);
+        proof_preimage.append(&strategy);
+        proof_preimage.append(&trade_params);
+        proof_preimage.append(&proof_salt);
+        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+
+        // A wrong commit salt reverts the proof reveal too
+        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
+        assert!(client
+            .try_reveal_proof_and_commit(&proof_id, &strategy, &trade_params, &proof_salt, &bad_salt)
+            .is_err());
+        assert!(!client.get_proof(&proof_id).revealed);
+
+        client.reveal_proof_and_commit(&proof_id, &strategy, &trade_params, &proof_salt, &commit_salt);
+
+        let proof = client.get_proof(&proof_id);
+        assert!(proof.revealed);
+        assert_eq!(proof.trade_params, trade_params);
+        let commit = client.get(&commit_id);
+        assert!(commit.revealed);
+        assert_eq!(commit.strategy, strategy);
+    }
+
+    #[test]
+    fn test_list_page_size_capped() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let proof_hash: BytesN<32> = BytesN::from_array(&env, &[2u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+
+        let cap = client.max_page_size();
+        for _ in 0..cap + 5 {
+            let id = client.commit(&owner, &commitment);
+            client.attach_proof(&owner, &proof_hash, &id, &tx_hash);
+        }
+
+        assert_eq!(client.list_commitments(&owner, &0, &(cap * 2)).len(), cap);
+        assert_eq!(client.list_proofs(&owner, &0, &(cap * 2)).len(), cap);
+
+        // Second page holds the remainder, in order
+        let rest = client.list_commitments(&owner, &cap, &(cap * 2));
+        assert_eq!(rest.len(), 5);
+        assert_eq!(rest.get(0).unwrap().0, cap as u64);
+
+        // Cancelled commitments drop out of the index
+        client.cancel(&0);
+        assert_eq!(client.list_commitments(&owner, &0, &1).get(0).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_get_spec() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let spec = StrategySpec {
+            asset: Symbol::new(&env, "XLM"),
+            side: Symbol::new(&env, "sell"),
+            threshold: 70,
+        };
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&spec.clone().to_xdr(&env));
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+        assert_eq!(client.get_spec(&id), None);
+
+        client.reveal_spec(&id, &spec, &salt);
+        assert_eq!(client.get_spec(&id), Some(spec));
+
+        // Byte-revealed commitments have no typed form
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let mut raw_preimage = Bytes::new(&env);
+        raw_preimage.append(&strategy);
+        raw_preimage.append(&salt);
+        let raw_id = client.commit(&owner, &env.crypto().sha256(&raw_preimage).into());
+        client.reveal(&raw_id, &strategy, &salt);
+        assert_eq!(client.get_spec(&raw_id), None);
+    }
+
+    #[test]
+    fn test_poseidon_commit_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+
+        // Known-answer vector pinning the parameter set for off-chain provers
+        let commitment = poseidon::hash(&env, &preimage);
+        let expected: [u8; 32] = [
+            0x14, 0x41, 0x0e, 0x26, 0x44, 0x9c, 0x79, 0x91, 0x19, 0x9c, 0x0d, 0xf0, 0xaf, 0x78,
+            0xe1, 0x10, 0x84, 0x4b, 0x23, 0x0d, 0xdd, 0xfa, 0xbd, 0x16, 0x4a, 0x60, 0x6d, 0x31,
+            0x8c, 0x36, 0xf1, 0x91,
+        ];
+        assert_eq!(commitment, BytesN::from_array(&env, &expected));
+
+        let id = client.commit_with_alg(&owner, &commitment, &HashAlg::Poseidon);
+        assert_eq!(client.get(&id).hash_alg, HashAlg::Poseidon);
+
+        // The SHA-256 commitment of the same preimage does not match
+        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
+        assert!(client.try_reveal(&id, &strategy, &bad_salt).is_err());
+
+        client.reveal(&id, &strategy, &salt);
+        assert!(client.get(&id).revealed);
+    }
+
+    #[test]
+    fn test_poseidon_reference_vector() {
+        // poseidonperm_x5_254_3 test vector from the Poseidon reference
+        // implementation: permute([0, 1, 2])
+        let mut input = [[0u8; 32]; 3];
+        input[1][31] = 1;
+        input[2][31] = 2;
+        let expected = [
+            hex32("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"),
+            hex32("0fca49b798923ab0239de1c9e7a4a9a2210312b6a2f616d18b5a87f9b628ae29"),
+            hex32("0e7ae82e40091e63cbd4f16a6d16310b3729d4b6e138fcf54110e2867045a30c"),
+        ];
+        assert_eq!(poseidon::permute_be(input), expected);
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, o) in out.iter_mut().enumerate() {
+            *o = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_reveal_proof_in_bundle() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment = BytesN::from_array(&env, &[1u8; 32]);
+
+        let leaf = |proof_hash: &BytesN<32>| -> BytesN<32> {
+            let mut leaf = Bytes::from_array(&env, &[0u8]);
+            leaf.append(&proof_hash.clone().into());
+            env.crypto().sha256(&leaf).into()
+        };
+        let parent = |a: &BytesN<32>, b: &BytesN<32>| -> BytesN<32> {
+            let (lo, hi) = if a.to_array() <= b.to_array() { (a, b) } else { (b, a) };
+            let mut pair = Bytes::from_array(&env, &[1u8]);
+            pair.append(&lo.clone().into());
+            pair.append(&hi.clone().into());
+            env.crypto().sha256(&pair).into()
+        };
+
+        let leaves: [BytesN<32>; 4] = [
+            BytesN::from_array(&env, &[10u8; 32]),
+            BytesN::from_array(&env, &[20u8; 32]),
+            BytesN::from_array(&env, &[30u8; 32]),
+            BytesN::from_array(&env, &[40u8; 32]),
+        ];
+        let left = parent(&leaf(&leaves[0]), &leaf(&leaves[1]));
+        let right = parent(&leaf(&leaves[2]), &leaf(&leaves[3]));
+        let root = parent(&left, &right);
+
+        let commit_id = client.commit_with_proof_root(&owner, &commitment, &root);
+        assert_eq!(client.get(&commit_id).proof_root, Some(root.into()));
+
+        let mut path = Vec::new(&env);
+        path.push_back(leaf(&leaves[3]));
+        path.push_back(left.clone());
+        let proof_id = client.reveal_proof_in_bundle(&commit_id, &leaves[2], &path);
+
+        let proof = client.get_proof(&proof_id);
+        assert_eq!(proof.proof_hash, leaves[2]);
+        assert_eq!(proof.commit_id, commit_id);
+        assert_eq!(proof.owner, owner);
+
+        // A hash outside the bundle is rejected
+        let outsider = BytesN::from_array(&env, &[50u8; 32]);
+        assert_eq!(
+            client.try_reveal_proof_in_bundle(&commit_id, &outsider, &path),
+            Err(Ok(Error::NotInBundle))
+        );
+
+        // So is an internal node passed off as a leaf with a shorter path
+        let mut short = Vec::new(&env);
+        short.push_back(right.clone());
+        assert_eq!(
+            client.try_reveal_proof_in_bundle(&commit_id, &left, &short),
+            Err(Ok(Error::NotInBundle))
+        );
+
+        // Plain commitments carry no bundle
+        let plain = client.commit(&owner, &commitment);
+        assert_eq!(
+            client.try_reveal_proof_in_bundle(&plain, &leaves[2], &path),
+            Err(Ok(Error::NoProofRoot))
+        );
+
+        // A compliance hold blocks bundle proofs as it does attach_proof
+        let admin = Address::generate(&env);
+        client.init(&admin);
+        client.freeze_commitment(&commit_id);
+        let mut path = Vec::new(&env);
+        path.push_back(leaf(&leaves[2]));
+        path.push_back(left.clone());
+        assert_eq!(
+            client.try_reveal_proof_in_bundle(&commit_id, &leaves[3], &path),
+            Err(Ok(Error::Frozen))
+        );
+        client.unfreeze_commitment(&commit_id);
+        client.reveal_proof_in_bundle(&commit_id, &leaves[3], &path);
+    }
+
+    #[test]
+    fn test_hmac_commit_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // RFC 4231 test case 2
+        let salt = Bytes::from_slice(&env, b"Jefe");
+        let strategy = Bytes::from_slice(&env, b"what do ya want for nothing?");
+        let commitment = BytesN::from_array(
+            &env,
+            &[
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43,
+            ],
+        );
+
+        let id = client.commit_with_scheme(&owner, &commitment, &CommitScheme::Hmac);
+        assert_eq!(client.get(&id).scheme, CommitScheme::Hmac);
+
+        // The plain concatenation of the same inputs does not open it
+        let plain = client.commit(&owner, &commitment);
+        assert!(client.try_reveal(&plain, &strategy, &salt).is_err());
+
+        client.reveal(&id, &strategy, &salt);
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, strategy);
+    }
+
+    #[test]
+    fn test_rotate_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+
+        let commit_hash = |salt: &[u8]| -> BytesN<32> {
+            let mut preimage = Bytes::new(&env);
+            preimage.append(&strategy);
+            preimage.append(&Bytes::from_slice(&env, salt));
+            env.crypto().sha256(&preimage).into()
+        };
+        let old = commit_hash(b"leaked_salt");
+        let new = commit_hash(b"fresh_salt_5678");
+
+        let id = client.commit(&owner, &old);
+
+        // A proof over the wrong pair is rejected
+        let mut wrong = Bytes::new(&env);
+        wrong.append(&new.clone().into());
+        wrong.append(&old.clone().into());
+        let wrong_proof: BytesN<32> = env.crypto().sha256(&wrong).into();
+        assert_eq!(
+            client.try_rotate_commitment(&id, &new, &wrong_proof),
+            Err(Ok(Error::BadContinuityProof))
+        );
+        assert_eq!(client.get(&id).commitment, old);
+
+        let mut link = Bytes::new(&env);
+        link.append(&old.clone().into());
+        link.append(&new.clone().into());
+        let proof: BytesN<32> = env.crypto().sha256(&link).into();
+        client.rotate_commitment(&id, &new, &proof);
+        assert_eq!(client.get(&id).commitment, new);
+
+        // Only the new salt opens it now
+        let old_salt = Bytes::from_slice(&env, b"leaked_salt");
+        assert!(client.try_reveal(&id, &strategy, &old_salt).is_err());
+        client.reveal(&id, &strategy, &Bytes::from_slice(&env, b"fresh_salt_5678"));
+        assert!(client.get(&id).revealed);
+    }
+
+    #[test]
+    fn test_rotate_dual_and_compressed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let old_salt = Bytes::from_slice(&env, b"leaked_salt");
+        let new_salt = Bytes::from_slice(&env, b"fresh_salt_5678");
+        let preimage = |salt: &Bytes| -> Bytes {
+            let mut preimage = Bytes::new(&env);
+            preimage.append(&strategy);
+            preimage.append(salt);
+            preimage
+        };
+        let sha = |salt: &Bytes| -> BytesN<32> { env.crypto().sha256(&preimage(salt)).into() };
+        let keccak =
+            |salt: &Bytes| -> BytesN<32> { env.crypto().keccak256(&preimage(salt)).into() };
+        let proof = |old: &BytesN<32>, new: &BytesN<32>| -> BytesN<32> {
+            let mut link = Bytes::new(&env);
+            link.append(&old.clone().into());
+            link.append(&new.clone().into());
+            env.crypto().sha256(&link).into()
+        };
+        let (old, new) = (sha(&old_salt), sha(&new_salt));
+
+        // A dual commitment can't be rotated without its Keccak-256 hash,
+        // and a single one can't gain one
+        let dual = client.commit_dual(&owner, &old, &keccak(&old_salt));
+        assert_eq!(
+            client.try_rotate_commitment(&dual, &new, &proof(&old, &new)),
+            Err(Ok(Error::DualCommitment))
+        );
+        let single = client.commit(&owner, &old);
+        let new_keccak = keccak(&new_salt);
+        assert_eq!(
+            client.try_rotate_dual_commitment(&single, &new, &new_keccak, &proof(&old, &new)),
+            Err(Ok(Error::DualCommitment))
+        );
+
+        client.rotate_dual_commitment(&dual, &new, &new_keccak, &proof(&old, &new));
+        assert!(client.try_reveal(&dual, &strategy, &old_salt).is_err());
+        client.reveal(&dual, &strategy, &new_salt);
+        assert!(client.get(&dual).revealed);
+
+        // The compressed hash covers only the blob, so it survives rotation
+        let blob = Bytes::from_slice(&env, b"compressed strategy");
+        let compressed =
+            client.commit_compressed(&owner, &old, &env.crypto().sha256(&blob).into());
+        client.rotate_commitment(&compressed, &new, &proof(&old, &new));
+        client.reveal_compressed(&compressed, &strategy, &new_salt, &blob);
+        assert!(client.get(&compressed).compressed);
+    }
+
+    #[test]
+    fn test_latest_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        assert_eq!(client.latest_commitment(&owner), None);
+
+        let first = client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let second = client.commit(&owner, &BytesN::from_array(&env, &[2u8; 32]));
+        assert_eq!(client.latest_commitment(&owner), Some(second));
+
+        client.cancel(&second);
+        assert_eq!(client.latest_commitment(&owner), Some(first));
+
+        client.cancel(&first);
+        assert_eq!(client.latest_commitment(&owner), None);
+    }
+
+    #[test]
+    fn test_find_matching_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let target = client.commit(&owner, &commitment);
+        client.commit(&owner, &BytesN::from_array(&env, &[2u8; 32]));
+
+        assert_eq!(
+            client.find_matching_commitment(&owner, &strategy, &salt),
+            Some(target)
+        );
+        let other_salt = Bytes::from_slice(&env, b"other_salt_0000");
+        assert_eq!(
+            client.find_matching_commitment(&owner, &strategy, &other_salt),
+            None
+        );
+
+        // Revealed commitments no longer match
+        client.reveal(&target, &strategy, &salt);
+        assert_eq!(client.find_matching_commitment(&owner, &strategy, &salt), None);
+    }
+
+    #[test]
+    fn test_feed_spans_owners() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+
+        let a0 = client.commit(&alice, &BytesN::from_array(&env, &[1u8; 32]));
+        let b0 = client.commit(&bob, &BytesN::from_array(&env, &[2u8; 32]));
+        let a1 = client.commit(&alice, &BytesN::from_array(&env, &[3u8; 32]));
+        let b1 = client.commit(&bob, &BytesN::from_array(&env, &[4u8; 32]));
+        client.cancel(&a1);
+
+        let page = client.feed(&0, &10);
+        assert_eq!(page.len(), 3);
+        assert_eq!(page.get(0).unwrap().0, a0);
+        assert_eq!(page.get(0).unwrap().1.owner, alice);
+        assert_eq!(page.get(1).unwrap().0, b0);
+        assert_eq!(page.get(1).unwrap().1.owner, bob);
+        assert_eq!(page.get(2).unwrap().0, b1);
+
+        let page = client.feed(&1, &1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().0, b0);
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let env = Env::default();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        env.ledger().set_sequence_number(1234);
+        env.ledger().set_timestamp(1_700_000_000);
+
+        let before = env.events().all().len();
+        assert_eq!(client.heartbeat(), (1234, 1_700_000_000));
+        assert_eq!(env.events().all().len(), before + 1);
+    }
+
+    #[test]
+    fn test_freeze_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        client.init(&admin);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+        client.freeze_commitment(&id);
+        assert!(client.get(&id).frozen);
+
+        let frozen = soroban_sdk::Error::from_contract_error(Error::Frozen as u32);
+        assert_eq!(client.try_reveal(&id, &strategy, &salt), Err(Ok(frozen)));
+        assert_eq!(client.try_cancel(&id), Err(Ok(frozen)));
+        let proof_hash = BytesN::from_array(&env, &[9u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"tx");
+        assert_eq!(
+            client.try_attach_proof(&owner, &proof_hash, &id, &tx_hash),
+            Err(Ok(Error::Frozen))
+        );
+
+        client.unfreeze_commitment(&id);
+        client.reveal(&id, &strategy, &salt);
+        assert!(client.get(&id).revealed);
+    }
+
+    #[test]
+    fn test_find_proof_by_tx() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let first = client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let second = client.commit(&owner, &BytesN::from_array(&env, &[2u8; 32]));
+
+        let tx_hash = Bytes::from_slice(&env, b"abc123def456");
+        let other_tx = Bytes::from_slice(&env, b"fff000");
+        assert_eq!(client.find_proof_by_tx(&tx_hash), None);
+
+        let p0 = client.attach_proof(&owner, &BytesN::from_array(&env, &[7u8; 32]), &first, &tx_hash);
+        client.attach_proof(&owner, &BytesN::from_array(&env, &[8u8; 32]), &first, &other_tx);
+        let p2 = client.attach_proof(&owner, &BytesN::from_array(&env, &[9u8; 32]), &second, &tx_hash);
+
+        assert_eq!(client.find_proof_by_tx(&tx_hash), Some(p0));
+        let all = client.proofs_by_tx(&tx_hash);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all.get(1).unwrap(), p2);
+    }
+
+    #[test]
+    fn test_commitment_chain() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let root = client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let mid = client.commit_with_parent(&owner, &BytesN::from_array(&env, &[2u8; 32]), &root);
+        let tip = client.commit_with_parent(&owner, &BytesN::from_array(&env, &[3u8; 32]), &mid);
+        assert_eq!(client.get(&tip).parent_id, Some(mid));
+
+        let chain = client.commitment_chain(&tip);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.get(0).unwrap(), tip);
+        assert_eq!(chain.get(1).unwrap(), mid);
+        assert_eq!(chain.get(2).unwrap(), root);
+
+        assert_eq!(
+            client.try_commit_with_parent(&owner, &BytesN::from_array(&env, &[4u8; 32]), &99),
+            Err(Ok(Error::NotFound))
+        );
+
+        // A chain longer than the cap is cut off at exactly MAX_CHAIN_DEPTH
+        let mut long = tip;
+        for i in 0..MAX_CHAIN_DEPTH {
+            let commitment = BytesN::from_array(&env, &[10 + i as u8; 32]);
+            long = client.commit_with_parent(&owner, &commitment, &long);
+        }
+        let chain = client.commitment_chain(&long);
+        assert_eq!(chain.len(), MAX_CHAIN_DEPTH);
+        assert_eq!(chain.get(0).unwrap(), long);
+    }
+
+    #[test]
+    fn test_reveal_window() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        env.ledger().set_timestamp(1_000);
+        let early = client.commit_with_window(&owner, &commitment, &2_000, &3_000);
+        let late = client.commit_with_window(&owner, &commitment, &2_000, &3_000);
 
-        if computed != record.proof_hash {
-            panic!("proof hash mismatch");
+        let not_yet = soroban_sdk::Error::from_contract_error(Error::NotYetValid as u32);
+        let expired = soroban_sdk::Error::from_contract_error(Error::Expired as u32);
+
+        env.ledger().set_timestamp(1_999);
+        assert_eq!(client.try_reveal(&early, &strategy, &salt), Err(Ok(not_yet)));
+        env.ledger().set_timestamp(2_000);
+        client.reveal(&early, &strategy, &salt);
+        assert!(client.get(&early).revealed);
+
+        env.ledger().set_timestamp(3_001);
+        assert_eq!(client.try_reveal(&late, &strategy, &salt), Err(Ok(expired)));
+        env.ledger().set_timestamp(3_000);
+        client.reveal(&late, &strategy, &salt);
+        assert!(client.get(&late).revealed);
+    }
+
+    #[test]
+    fn test_estimate_reveal_footprint() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let small = client.estimate_reveal_footprint(&0);
+        let large = client.estimate_reveal_footprint(&1_000);
+        assert_eq!(small, REVEAL_FOOTPRINT_OVERHEAD);
+        assert_eq!(large - small, 1_000);
+        assert!(client.estimate_reveal_footprint(&1_001) > large);
+
+        // Matches what a reveal actually stores
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let id = client.commit(&owner, &env.crypto().sha256(&preimage).into());
+        client.reveal(&id, &strategy, &salt);
+
+        let stored = client.get(&id).to_xdr(&env).len()
+            + DataKey::Commitment(id).to_xdr(&env).len();
+        assert_eq!(client.estimate_reveal_footprint(&strategy.len()), stored);
+    }
+
+    #[test]
+    fn test_commit_dual() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let sha: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let keccak: BytesN<32> = env.crypto().keccak256(&preimage).into();
+
+        let id = client.commit_dual(&owner, &sha, &keccak);
+        client.reveal(&id, &strategy, &salt);
+        assert!(client.get(&id).revealed);
+
+        // A correct SHA-256 with a wrong Keccak-256 is not enough
+        let bad = client.commit_dual(&owner, &sha, &BytesN::from_array(&env, &[0u8; 32]));
+        assert!(client.try_reveal(&bad, &strategy, &salt).is_err());
+        assert!(!client.get(&bad).revealed);
+    }
+
+    #[test]
+    fn test_commits_on_day() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+
+        // Day 19_700, late evening
+        env.ledger().set_timestamp(19_700 * 86_400 + 80_000);
+        let a = client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let b = client.commit(&owner, &BytesN::from_array(&env, &[2u8; 32]));
+
+        // Next day
+        env.ledger().set_timestamp(19_701 * 86_400 + 10);
+        let c = client.commit(&owner, &BytesN::from_array(&env, &[3u8; 32]));
+
+        let first = client.commits_on_day(&19_700);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first.get(0).unwrap(), a);
+        assert_eq!(first.get(1).unwrap(), b);
+
+        let second = client.commits_on_day(&19_701);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second.get(0).unwrap(), c);
+
+        assert_eq!(client.commits_on_day(&19_702).len(), 0);
+    }
+
+    #[test]
+    fn test_record_pnl() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commit_id = client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        let attach = |trade_params: &Bytes, salt: &Bytes| -> u64 {
+            let mut preimage = Bytes::new(&env);
+            preimage.append(&strategy);
+            preimage.append(trade_params);
+            preimage.append(salt);
+            let proof_hash: BytesN<32> = env.crypto().sha256(&preimage).into();
+            client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash)
+        };
+
+        let win_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let win_salt = Bytes::from_slice(&env, b"proof_salt_0001");
+        let win = attach(&win_params, &win_salt);
+        let loss_params = Bytes::from_slice(&env, b"buy:XLM:200");
+        let loss_salt = Bytes::from_slice(&env, b"proof_salt_0002");
+        let loss = attach(&loss_params, &loss_salt);
+
+        // Not allowed before reveal
+        assert_eq!(client.try_record_pnl(&win, &1), Err(Ok(Error::ProofNotRevealed)));
+
+        client.reveal_proof(&win, &strategy, &win_params, &win_salt);
+        client.reveal_proof(&loss, &strategy, &loss_params, &loss_salt);
+        client.record_pnl(&win, &1_250);
+        client.record_pnl(&loss, &-430);
+
+        assert_eq!(client.get_pnl(&win), Some(1_250));
+        assert_eq!(client.get_pnl(&loss), Some(-430));
+        assert_eq!(client.get_proof(&loss).pnl, Some(-430));
+        assert_eq!(client.get_pnl(&99), None);
+    }
+
+    #[test]
+    fn test_strict_proofs_block_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        client.init(&admin);
+        client.set_strict_proofs(&true);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commit_id = client.commit(&owner, &env.crypto().sha256(&preimage).into());
+
+        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
+        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
+        let mut proof_preimage = Bytes::new(&env);
+        proof_preimage.append(&strategy);
+        proof_preimage.append(&trade_params);
+        proof_preimage.append(&proof_salt);
+        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+
+        let pending = soroban_sdk::Error::from_contract_error(Error::PendingProofs as u32);
+        assert_eq!(client.try_reveal(&commit_id, &strategy, &salt), Err(Ok(pending)));
+
+        client.reveal_proof(&proof_id, &strategy, &trade_params, &proof_salt);
+        client.reveal(&commit_id, &strategy, &salt);
+        assert!(client.get(&commit_id).revealed);
+    }
+
+    #[test]
+    fn test_pending_proofs_allowed_by_default() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        assert!(!client.strict_proofs());
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&strategy);
+        preimage.append(&salt);
+        let commit_id = client.commit(&owner, &env.crypto().sha256(&preimage).into());
+
+        let proof_hash = BytesN::from_array(&env, &[7u8; 32]);
+        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+        client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+
+        client.reveal(&commit_id, &strategy, &salt);
+        assert!(client.get(&commit_id).revealed);
+    }
+
+    #[test]
+    fn test_commitment_count() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        assert_eq!(client.commitment_count(&owner), 0);
+
+        client.commit(&owner, &BytesN::from_array(&env, &[1u8; 32]));
+        let second = client.commit(&owner, &BytesN::from_array(&env, &[2u8; 32]));
+        client.commit(&owner, &BytesN::from_array(&env, &[3u8; 32]));
+        assert_eq!(client.commitment_count(&owner), 3);
+
+        client.cancel(&second);
+        assert_eq!(client.commitment_count(&owner), 2);
+        assert_eq!(client.list_commitments(&owner, &0, &10).len(), 2);
+    }
+
+    #[test]
+    fn test_reveal_two_salt() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let public_salt = Bytes::from_slice(&env, b"nonce-0001");
+        let private_salt = Bytes::from_slice(&env, b"secret_salt_5678");
+
+        let mut preimage = strategy.clone();
+        preimage.append(&public_salt);
+        preimage.append(&private_salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+        assert_eq!(client.get_public_salt(&id), None);
+
+        client.reveal_two_salt(&id, &strategy, &public_salt, &private_salt);
+
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, strategy);
+        assert_eq!(client.get_public_salt(&id), Some(public_salt));
+    }
+
+    #[test]
+    #[should_panic(expected = "hash mismatch")]
+    fn test_reveal_two_salt_wrong_private_salt() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let public_salt = Bytes::from_slice(&env, b"nonce-0001");
+
+        let mut preimage = strategy.clone();
+        preimage.append(&public_salt);
+        preimage.append(&Bytes::from_slice(&env, b"secret_salt_5678"));
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = client.commit(&owner, &commitment);
+        let wrong = Bytes::from_slice(&env, b"secret_salt_0000");
+        client.reveal_two_salt(&id, &strategy, &public_salt, &wrong);
+    }
+
+    /// Minimal smart wallet: a contract account that owns commitments and
+    /// authorizes calls it makes itself, without any ed25519 signature.
+    #[contract]
+    struct MockWallet;
+
+    #[contractimpl]
+    impl MockWallet {
+        pub fn commit(env: Env, registry: Address, commitment: BytesN<32>) -> u64 {
+            StrategyCommitmentClient::new(&env, &registry)
+                .commit(&env.current_contract_address(), &commitment)
+        }
+
+        pub fn reveal(env: Env, registry: Address, commit_id: u64, strategy: Bytes, salt: Bytes) {
+            StrategyCommitmentClient::new(&env, &registry).reveal(&commit_id, &strategy, &salt);
+        }
+    }
+
+    #[test]
+    fn test_contract_owner_reveal() {
+        // No mock_all_auths: the wallet's require_auth is satisfied only
+        // because the wallet itself is the direct invoker.
+        let env = Env::default();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+        let wallet_id = env.register_contract(None, MockWallet);
+        let wallet = MockWalletClient::new(&env, &wallet_id);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = strategy.clone();
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let id = wallet.commit(&contract_id, &commitment);
+        assert_eq!(client.get(&id).owner, wallet_id);
+
+        wallet.reveal(&contract_id, &id, &strategy, &salt);
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, strategy);
+        assert_eq!(client.latest_commitment(&wallet_id), Some(id));
+    }
+
+    #[test]
+    fn test_reveal_acl() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let mallory = Address::generate(&env);
+
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let mut preimage = strategy.clone();
+        preimage.append(&salt);
+        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let first = client.commit(&owner, &commitment);
+        let second = client.commit(&owner, &commitment);
+        let acl = Vec::from_array(&env, [alice.clone(), bob.clone()]);
+        client.set_reveal_acl(&first, &acl);
+        client.set_reveal_acl(&second, &acl);
+        assert_eq!(client.reveal_acl(&first), acl);
+
+        assert_eq!(
+            client.try_reveal_as(&mallory, &first, &strategy, &salt),
+            Err(Ok(soroban_sdk::Error::from_contract_error(Error::NotRevealer as u32)))
+        );
+
+        client.reveal_as(&alice, &first, &strategy, &salt);
+        assert_eq!(env.auths()[0].0, alice);
+        client.reveal_as(&bob, &second, &strategy, &salt);
+        assert_eq!(env.auths()[0].0, bob);
+        assert!(client.get(&first).revealed);
+        assert!(client.get(&second).revealed);
+
+        let mut too_many = Vec::new(&env);
+        for _ in 0..=MAX_REVEAL_ACL {
+            too_many.push_back(Address::generate(&env));
         }
+        let third = client.commit(&owner, &commitment);
+        assert_eq!(
+            client.try_set_reveal_acl(&third, &too_many),
+            Err(Ok(Error::AclTooLarge))
+        );
+    }
+
+    #[test]
+    fn test_purge_expired_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
 
-        record.revealed = true;
-        record.strategy = strategy;
-        record.trade_params = trade_params;
+        let owner = Address::generate(&env);
+        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Proof(proof_id), &record);
+        env.ledger().set_timestamp(1_000);
+        let expired_a = client.commit_with_deadline(&owner, &commitment, &2_000);
+        let expired_b = client.commit_with_deadline(&owner, &commitment, &2_500);
+        let live = client.commit_with_deadline(&owner, &commitment, &5_000);
+        let no_deadline = client.commit(&owner, &commitment);
 
-        env.events()
-            .publish((symbol_short!("p_reveal"),), (proof_id, record.owner));
-    }
-}
+        env.ledger().set_timestamp(3_000);
+        let ids = Vec::from_array(&env, [expired_a, live, 99, no_deadline, expired_b]);
+        assert_eq!(client.purge_expired_batch(&ids), 2);
 
-// ─── Tests ───────────────────────────────────────────────────────────────────
+        assert!(client.try_get(&expired_a).is_err());
+        assert!(client.try_get(&expired_b).is_err());
+        assert!(!client.get(&live).revealed);
+        assert!(!client.get(&no_deadline).revealed);
+        assert_eq!(client.commitment_count(&owner), 2);
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{testutils::Address as _, Env};
+        // Already purged ids are skipped
+        assert_eq!(client.purge_expired_batch(&ids), 0);
+    }
 
     #[test]
-    fn test_commit_get_reveal() {
+    fn test_admin_reveal() {
         let env = Env::default();
         env.mock_all_auths();
 
         let contract_id = env.register_contract(None, StrategyCommitment);
         let client = StrategyCommitmentClient::new(&env, &contract_id);
 
+        let admin = Address::generate(&env);
         let owner = Address::generate(&env);
+        client.init(&admin);
 
-        // Build commitment off-chain: sha256(strategy || salt)
         let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
         let salt = Bytes::from_slice(&env, b"random_salt_1234");
-
-        let mut preimage = Bytes::new(&env);
-        preimage.append(&strategy);
+        let mut preimage = strategy.clone();
         preimage.append(&salt);
         let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
-
-        // 1. Commit
         let id = client.commit(&owner, &commitment);
-        assert_eq!(id, 0);
 
-        // 2. Get
-        let record = client.get(&id);
-        assert_eq!(record.owner, owner);
-        assert_eq!(record.commitment, commitment);
-        assert!(!record.revealed);
+        // Wrong preimage is still rejected
+        let bad_salt = Bytes::from_slice(&env, b"random_salt_9999");
+        assert!(client.try_admin_reveal(&id, &strategy, &bad_salt).is_err());
 
-        // 3. Reveal
-        client.reveal(&id, &strategy, &salt);
+        client.admin_reveal(&id, &strategy, &salt);
+        assert_eq!(env.auths()[0].0, admin);
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert_eq!(record.strategy, strategy);
 
-        let revealed = client.get(&id);
-        assert!(revealed.revealed);
-        assert_eq!(revealed.strategy, strategy);
+        let last = env.events().all().last().unwrap();
+        assert_eq!(last.0, contract_id);
+        assert_eq!(last.1, (symbol_short!("admin_rvl"),).into_val(&env));
     }
 
     #[test]
-    #[should_panic(expected = "hash mismatch")]
-    fn test_bad_reveal() {
+    fn test_revealed_strategies() {
         let env = Env::default();
         env.mock_all_auths();
 
@@ -321,24 +3514,106 @@ mod test {
         let client = StrategyCommitmentClient::new(&env, &contract_id);
 
         let owner = Address::generate(&env);
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let strategies = [
+            Bytes::from_slice(&env, b"buy XLM when RSI < 30"),
+            Bytes::from_slice(&env, b"sell XLM when RSI > 70"),
+            Bytes::from_slice(&env, b"hold"),
+        ];
+
+        let mut ids = [0u64; 3];
+        for (i, strategy) in strategies.iter().enumerate() {
+            let mut preimage = strategy.clone();
+            preimage.append(&salt);
+            ids[i] = client.commit(&owner, &env.crypto().sha256(&preimage).into());
+        }
+        client.reveal(&ids[0], &strategies[0], &salt);
+        client.reveal(&ids[2], &strategies[2], &salt);
+
+        let revealed = client.revealed_strategies(&owner, &0, &10);
+        assert_eq!(
+            revealed,
+            Vec::from_array(
+                &env,
+                [(ids[0], strategies[0].clone()), (ids[2], strategies[2].clone())]
+            )
+        );
+
+        let rest = client.revealed_strategies(&owner, &(ids[0] + 1), &10);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest.get(0).unwrap().0, ids[2]);
+    }
+
+    #[test]
+    fn test_compute_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
 
+        let owner = Address::generate(&env);
         let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
         let salt = Bytes::from_slice(&env, b"random_salt_1234");
 
+        // Same construction as test_commit_get_reveal
         let mut preimage = Bytes::new(&env);
         preimage.append(&strategy);
         preimage.append(&salt);
-        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let expected: BytesN<32> = env.crypto().sha256(&preimage).into();
+
+        let commitment = client.compute_commitment(&strategy, &salt);
+        assert_eq!(commitment, expected);
 
         let id = client.commit(&owner, &commitment);
+        client.reveal(&id, &strategy, &salt);
+        assert!(client.get(&id).revealed);
+    }
 
-        // Try reveal with wrong salt
-        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
-        client.reveal(&id, &strategy, &bad_salt);
+    #[test]
+    fn test_reveal_compressed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbb");
+        // Toy run-length encoding standing in for the client's codec
+        let compressed = Bytes::from_slice(&env, &[32, b'a', 16, b'b']);
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+
+        let commitment = client.compute_commitment(&strategy, &salt);
+        let compressed_hash: BytesN<32> = env.crypto().sha256(&compressed).into();
+        let id = client.commit_compressed(&owner, &commitment, &compressed_hash);
+
+        // A blob other than the committed one is rejected
+        let other = Bytes::from_slice(&env, &[31, b'a', 17, b'b']);
+        assert!(client
+            .try_reveal_compressed(&id, &strategy, &salt, &other)
+            .is_err());
+
+        client.reveal_compressed(&id, &strategy, &salt, &compressed);
+        let record = client.get(&id);
+        assert!(record.revealed);
+        assert!(record.compressed);
+        assert_eq!(record.strategy, compressed);
+        let raw_hash: BytesN<32> = env.crypto().sha256(&strategy).into();
+        assert_eq!(client.strategy_hash(&id), Some(raw_hash));
+
+        // Plain commitments can't take the compressed path
+        let plain = client.commit(&owner, &commitment);
+        assert_eq!(
+            client.try_reveal_compressed(&plain, &strategy, &salt, &compressed),
+            Err(Ok(soroban_sdk::Error::from_contract_error(Error::NotCompressed as u32)))
+        );
     }
 
     #[test]
-    fn test_attach_and_get_proof() {
+    fn test_commitment_xdr_round_trip() {
+        use soroban_sdk::xdr::FromXdr;
+
         let env = Env::default();
         env.mock_all_auths();
 
@@ -346,41 +3621,80 @@ mod test {
         let client = StrategyCommitmentClient::new(&env, &contract_id);
 
         let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let id = client.commit(&owner, &client.compute_commitment(&strategy, &salt));
+        client.reveal(&id, &strategy, &salt);
 
-        // First create a commitment
+        let xdr = client.commitment_xdr(&id);
+        let decoded = CommitmentRecord::from_xdr(&env, &xdr).unwrap();
+        let record = client.get(&id);
+        assert_eq!(decoded.owner, record.owner);
+        assert_eq!(decoded.commitment, record.commitment);
+        assert_eq!(decoded.strategy, strategy);
+        assert!(decoded.revealed);
+        assert_eq!(decoded.to_xdr(&env), xdr);
+    }
+
+    #[test]
+    fn test_reveal_receipt() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
         let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
         let salt = Bytes::from_slice(&env, b"random_salt_1234");
-        let mut preimage = Bytes::new(&env);
-        preimage.append(&strategy);
-        preimage.append(&salt);
-        let commitment: BytesN<32> = env.crypto().sha256(&preimage).into();
-        let commit_id = client.commit(&owner, &commitment);
 
-        // Build proof hash: sha256(strategy || trade_params || proof_salt)
-        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
-        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
-        let mut proof_preimage = Bytes::new(&env);
-        proof_preimage.append(&strategy);
-        proof_preimage.append(&trade_params);
-        proof_preimage.append(&proof_salt);
-        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+        env.ledger().set_timestamp(1_000);
+        let id = client.commit(&owner, &client.compute_commitment(&strategy, &salt));
+
+        env.ledger().set_timestamp(1_500);
+        let receipt = client.reveal(&id, &strategy, &salt);
+        assert_eq!(
+            receipt,
+            RevealReceipt {
+                commit_id: id,
+                revealed_at: 1_500,
+                strategy_len: strategy.len(),
+            }
+        );
+    }
 
-        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
+    #[test]
+    fn test_reveal_statuses() {
+        let env = Env::default();
+        env.mock_all_auths();
 
-        // Attach proof
-        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
-        assert_eq!(proof_id, 0);
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
 
-        // Get proof
-        let record = client.get_proof(&proof_id);
-        assert_eq!(record.owner, owner);
-        assert_eq!(record.proof_hash, proof_hash);
-        assert_eq!(record.commit_id, commit_id);
-        assert!(!record.revealed);
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let commitment = client.compute_commitment(&strategy, &salt);
+
+        let revealed = client.commit(&owner, &commitment);
+        let pending = client.commit(&owner, &commitment);
+        client.reveal(&revealed, &strategy, &salt);
+
+        let ids = Vec::from_array(&env, [pending, 42, revealed]);
+        assert_eq!(
+            client.reveal_statuses(&ids),
+            Vec::from_array(&env, [false, false, true])
+        );
+
+        let mut many = Vec::new(&env);
+        for _ in 0..(MAX_PAGE_SIZE + 5) {
+            many.push_back(revealed);
+        }
+        assert_eq!(client.reveal_statuses(&many).len(), MAX_PAGE_SIZE);
     }
 
     #[test]
-    fn test_reveal_proof() {
+    fn test_list_by_tags() {
         let env = Env::default();
         env.mock_all_auths();
 
@@ -388,81 +3702,350 @@ mod test {
         let client = StrategyCommitmentClient::new(&env, &contract_id);
 
         let owner = Address::generate(&env);
+        let commitment = BytesN::from_array(&env, &[1u8; 32]);
+        let xlm = symbol_short!("xlm");
+        let momentum = symbol_short!("momentum");
+        let hedge = symbol_short!("hedge");
+
+        let tags_a = Vec::from_array(&env, [xlm.clone(), momentum.clone()]);
+        let a = client.commit_with_tags(&owner, &commitment, &tags_a);
+        let b = client.commit_with_tags(&owner, &commitment, &Vec::from_array(&env, [xlm.clone()]));
+        let tags_c = Vec::from_array(&env, [hedge.clone()]);
+        let c = client.commit_with_tags(&owner, &commitment, &tags_c);
+        client.commit(&owner, &commitment);
+
+        let ids = |page: Vec<(u64, CommitmentRecord)>| {
+            let mut ids = Vec::new(&env);
+            for (id, _) in page.iter() {
+                ids.push_back(id);
+            }
+            ids
+        };
 
-        // Create commitment
-        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
-        let commit_id = client.commit(&owner, &commitment);
+        // AND: only `a` carries both; OR: `b` matches on xlm alone
+        let all = ids(client.list_by_tags(&owner, &tags_a, &true, &0, &10));
+        assert_eq!(all, Vec::from_array(&env, [a]));
+        let any = ids(client.list_by_tags(&owner, &tags_a, &false, &0, &10));
+        assert_eq!(any, Vec::from_array(&env, [a, b]));
 
-        // Build proof
-        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
-        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
-        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
-        let mut proof_preimage = Bytes::new(&env);
-        proof_preimage.append(&strategy);
-        proof_preimage.append(&trade_params);
-        proof_preimage.append(&proof_salt);
-        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+        let either = Vec::from_array(&env, [momentum, hedge]);
+        let any = ids(client.list_by_tags(&owner, &either, &false, &0, &10));
+        assert_eq!(any, Vec::from_array(&env, [a, c]));
 
-        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
-        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+        let mut too_many = Vec::new(&env);
+        for _ in 0..=MAX_TAGS {
+            too_many.push_back(xlm.clone());
+        }
+        assert_eq!(
+            client.try_commit_with_tags(&owner, &commitment, &too_many),
+            Err(Ok(Error::TooManyTags))
+        );
+    }
 
-        // Reveal
-        client.reveal_proof(&proof_id, &strategy, &trade_params, &proof_salt);
+    fn armed_setup(
+        env: &Env,
+    ) -> (StrategyCommitmentClient<'_>, u64, Bytes, Bytes, Address) {
+        env.mock_all_auths();
 
-        let record = client.get_proof(&proof_id);
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(env, &contract_id);
+
+        let owner = Address::generate(env);
+        let keeper = Address::generate(env);
+        let strategy = Bytes::from_slice(env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(env, b"random_salt_1234");
+        let id = client.commit(&owner, &client.compute_commitment(&strategy, &salt));
+
+        let mut intent = strategy.clone();
+        intent.append(&salt);
+        intent.append(&keeper.clone().to_xdr(env));
+        client.arm_reveal(&id, &env.crypto().sha256(&intent).into());
+
+        (client, id, strategy, salt, keeper)
+    }
+
+    #[test]
+    fn test_reveal_armed() {
+        let env = Env::default();
+        let (client, id, strategy, salt, keeper) = armed_setup(&env);
+
+        client.reveal_armed(&keeper, &id, &strategy, &salt);
+        assert_eq!(env.auths()[0].0, keeper);
+        let record = client.get(&id);
         assert!(record.revealed);
         assert_eq!(record.strategy, strategy);
-        assert_eq!(record.trade_params, trade_params);
     }
 
     #[test]
-    #[should_panic(expected = "proof hash mismatch")]
-    fn test_bad_reveal_proof() {
+    fn test_reveal_armed_wrong_keeper() {
+        let env = Env::default();
+        let (client, id, strategy, salt, _) = armed_setup(&env);
+
+        let other = Address::generate(&env);
+        assert_eq!(
+            client.try_reveal_armed(&other, &id, &strategy, &salt),
+            Err(Ok(soroban_sdk::Error::from_contract_error(Error::IntentMismatch as u32)))
+        );
+        assert!(!client.get(&id).revealed);
+    }
+
+    /// Verifier that approves only strategies starting with "buy".
+    #[contract]
+    struct MockVerifier;
+
+    #[contractimpl]
+    impl MockVerifier {
+        pub fn verify(env: Env, strategy: Bytes, _trade_params: Bytes) -> bool {
+            strategy.len() >= 3 && strategy.slice(0..3) == Bytes::from_slice(&env, b"buy")
+        }
+    }
+
+    #[test]
+    fn test_reveal_with_verifier() {
         let env = Env::default();
         env.mock_all_auths();
 
         let contract_id = env.register_contract(None, StrategyCommitment);
         let client = StrategyCommitmentClient::new(&env, &contract_id);
+        let verifier = env.register_contract(None, MockVerifier);
 
         let owner = Address::generate(&env);
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let good = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let bad = Bytes::from_slice(&env, b"sell XLM when RSI > 70");
+
+        let good_id = client.commit_with_verifier(
+            &owner,
+            &client.compute_commitment(&good, &salt),
+            &verifier,
+        );
+        let bad_id = client.commit_with_verifier(
+            &owner,
+            &client.compute_commitment(&bad, &salt),
+            &verifier,
+        );
+
+        client.reveal(&good_id, &good, &salt);
+        assert!(client.get(&good_id).revealed);
+
+        let res = client.try_reveal(&bad_id, &bad, &salt);
+        assert_eq!(
+            res,
+            Err(Ok(soroban_sdk::Error::from_contract_error(Error::VerifierRejected as u32)))
+        );
+        assert!(!client.get(&bad_id).revealed);
+    }
 
-        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
-        let commit_id = client.commit(&owner, &commitment);
+    #[test]
+    fn test_cancel_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
 
+        let owner = Address::generate(&env);
+        let other = Address::generate(&env);
         let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
-        let trade_params = Bytes::from_slice(&env, b"buy:XLM:100");
-        let proof_salt = Bytes::from_slice(&env, b"proof_salt_5678");
-        let mut proof_preimage = Bytes::new(&env);
-        proof_preimage.append(&strategy);
-        proof_preimage.append(&trade_params);
-        proof_preimage.append(&proof_salt);
-        let proof_hash: BytesN<32> = env.crypto().sha256(&proof_preimage).into();
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let commitment = client.compute_commitment(&strategy, &salt);
+
+        let a = client.commit(&owner, &commitment);
+        let b = client.commit(&owner, &commitment);
+        let revealed = client.commit(&owner, &commitment);
+        let foreign = client.commit(&other, &commitment);
+        let kept = client.commit(&owner, &commitment);
+        client.reveal(&revealed, &strategy, &salt);
+
+        let ids = Vec::from_array(&env, [a, b, revealed, foreign, 999]);
+        assert_eq!(client.cancel_batch(&owner, &ids), 2);
+
+        assert!(client.try_get(&a).is_err());
+        assert!(client.try_get(&b).is_err());
+        assert!(client.get(&revealed).revealed);
+        assert_eq!(client.get(&foreign).owner, other);
+        assert!(!client.get(&kept).revealed);
+        assert_eq!(client.commitment_count(&owner), 2);
+    }
 
-        let tx_hash = Bytes::from_slice(&env, b"abc123txhash");
-        let proof_id = client.attach_proof(&owner, &proof_hash, &commit_id, &tx_hash);
+    #[test]
+    fn test_open_commitments() {
+        let env = Env::default();
+        env.mock_all_auths();
 
-        // Reveal with wrong salt
-        let bad_salt = Bytes::from_slice(&env, b"wrong_salt");
-        client.reveal_proof(&proof_id, &strategy, &trade_params, &bad_salt);
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let commitment = client.compute_commitment(&strategy, &salt);
+
+        assert_eq!(client.open_commitments(&owner), 0);
+        let a = client.commit(&owner, &commitment);
+        let b = client.commit(&owner, &commitment);
+        client.commit(&owner, &commitment);
+        assert_eq!(client.open_commitments(&owner), 3);
+
+        client.reveal(&a, &strategy, &salt);
+        client.reveal(&b, &strategy, &salt);
+        assert_eq!(client.open_commitments(&owner), 1);
+        assert_eq!(client.commitment_count(&owner), 3);
     }
 
     #[test]
-    fn test_multiple_commits() {
+    fn test_reveal_fee() {
         let env = Env::default();
         env.mock_all_auths();
 
         let contract_id = env.register_contract(None, StrategyCommitment);
         let client = StrategyCommitmentClient::new(&env, &contract_id);
 
+        let admin = Address::generate(&env);
         let owner = Address::generate(&env);
-        let commitment: BytesN<32> = BytesN::from_array(&env, &[1u8; 32]);
+        let collector = Address::generate(&env);
+        let fee_token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let balances = token::Client::new(&env, &fee_token);
+        token::StellarAssetClient::new(&env, &fee_token).mint(&owner, &150);
 
-        let id0 = client.commit(&owner, &commitment);
-        let id1 = client.commit(&owner, &commitment);
-        let id2 = client.commit(&owner, &commitment);
+        client.init(&admin);
+        assert_eq!(client.reveal_fee(), None);
+        client.set_reveal_fee(&fee_token, &100, &collector);
 
-        assert_eq!(id0, 0);
-        assert_eq!(id1, 1);
-        assert_eq!(id2, 2);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let commitment = client.compute_commitment(&strategy, &salt);
+        let paid = client.commit(&owner, &commitment);
+        let unpaid = client.commit(&owner, &commitment);
+
+        client.reveal(&paid, &strategy, &salt);
+        assert_eq!(balances.balance(&owner), 50);
+        assert_eq!(balances.balance(&collector), 100);
+
+        // Can't cover a second fee: the reveal reverts
+        assert!(client.try_reveal(&unpaid, &strategy, &salt).is_err());
+        assert!(!client.get(&unpaid).revealed);
+        assert_eq!(balances.balance(&owner), 50);
+    }
+
+    #[test]
+    fn test_find_by_hash() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let strategy = Bytes::from_slice(&env, b"buy XLM when RSI < 30");
+        let salt = Bytes::from_slice(&env, b"random_salt_1234");
+        let commitment = client.compute_commitment(&strategy, &salt);
+        let other = BytesN::from_array(&env, &[7u8; 32]);
+
+        let a = client.commit(&alice, &commitment);
+        client.commit(&alice, &other);
+        let b = client.commit(&bob, &commitment);
+
+        assert_eq!(client.find_by_hash(&commitment), Vec::from_array(&env, [a, b]));
+        assert_eq!(client.find_by_hash(&BytesN::from_array(&env, &[0u8; 32])).len(), 0);
+
+        client.cancel(&a);
+        assert_eq!(client.find_by_hash(&commitment), Vec::from_array(&env, [b]));
+    }
+
+    #[test]
+    fn test_register_expectation() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let counterparty = Address::generate(&env);
+        let other = Address::generate(&env);
+        let commitment = BytesN::from_array(&env, &[7u8; 32]);
+
+        // A third party registers what they expect the owner to commit
+        client.register_expectation(&counterparty, &owner, &commitment);
+        assert_eq!(env.auths()[0].0, counterparty);
+        assert!(client.is_expected(&counterparty, &owner, &commitment));
+
+        let event = env.events().all().last().unwrap();
+        assert_eq!(event.1, (symbol_short!("expect"),).into_val(&env));
+        let data: (Address, Address, BytesN<32>) = event.2.into_val(&env);
+        assert_eq!(data, (counterparty.clone(), owner.clone(), commitment.clone()));
+
+        // Same hash from someone else, or another hash from the owner,
+        // doesn't meet it
+        client.commit(&other, &commitment);
+        client.commit(&owner, &BytesN::from_array(&env, &[8u8; 32]));
+        assert!(client.is_expected(&counterparty, &owner, &commitment));
+
+        let id = client.commit(&owner, &commitment);
+        assert!(!client.is_expected(&counterparty, &owner, &commitment));
+
+        let event = env.events().all().last().unwrap();
+        assert_eq!(event.1, (symbol_short!("exp_met"),).into_val(&env));
+        let data: (u64, Address, BytesN<32>, Address) = event.2.into_val(&env);
+        assert_eq!(data, (id, owner.clone(), commitment.clone(), counterparty.clone()));
+
+        // Only the registrant can cancel their expectation
+        client.register_expectation(&counterparty, &owner, &commitment);
+        assert_eq!(
+            client.try_cancel_expectation(&other, &owner, &commitment),
+            Err(Ok(soroban_sdk::Error::from_contract_error(Error::NotFound as u32)))
+        );
+        assert!(client.is_expected(&counterparty, &owner, &commitment));
+
+        client.cancel_expectation(&counterparty, &owner, &commitment);
+        assert_eq!(env.auths()[0].0, counterparty);
+        assert!(!client.is_expected(&counterparty, &owner, &commitment));
+
+        // A cancelled expectation is not met by a later commit
+        client.commit(&owner, &commitment);
+        let event = env.events().all().last().unwrap();
+        assert_eq!(event.1, (symbol_short!("commit"),).into_val(&env));
+    }
+
+    #[test]
+    fn test_expectations_capped() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let owner = Address::generate(&env);
+        let commitment = BytesN::from_array(&env, &[7u8; 32]);
+        for _ in 0..MAX_EXPECTATIONS {
+            client.register_expectation(&Address::generate(&env), &owner, &commitment);
+        }
+        assert_eq!(
+            client.try_register_expectation(&Address::generate(&env), &owner, &commitment),
+            Err(Ok(soroban_sdk::Error::from_contract_error(
+                Error::TooManyExpectations as u32
+            )))
+        );
+    }
+
+    #[test]
+    fn test_derive_salt() {
+        let env = Env::default();
+
+        let contract_id = env.register_contract(None, StrategyCommitment);
+        let client = StrategyCommitmentClient::new(&env, &contract_id);
+
+        let seed = Bytes::from_slice(&env, b"master seed");
+        let first = client.derive_salt(&seed, &0);
+        assert_eq!(client.derive_salt(&seed, &0), first);
+        assert_ne!(client.derive_salt(&seed, &1), first);
+        assert_ne!(client.derive_salt(&Bytes::from_slice(&env, b"other seed"), &0), first);
+
+        let mut preimage = seed.clone();
+        preimage.extend_from_array(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        let expected: BytesN<32> = env.crypto().sha256(&preimage).into();
+        assert_eq!(client.derive_salt(&seed, &1), expected);
     }
 }